@@ -0,0 +1,295 @@
+// Evaluation of integer constant expressions (C11 6.6), as needed for `case`
+// labels, array sizes and enumerator values. Returns None for anything that
+// isn't a constant, including division by zero and out of range shifts.
+use crate::diagnostics::{self, Diagnostic};
+use crate::lexer::TokenType;
+use crate::parser::*;
+use crate::span::Span;
+use crate::types::Type;
+
+// What evaluation needs to know about the declarations in scope.
+pub trait Scope {
+    // `ty` with its struct, union and enum tags looked up.
+    fn resolve(&mut self, ty: &Type) -> Type;
+    // The value of the enumerator `name`, if that is what it names.
+    fn enumerator(&mut self, name: &str) -> Option<i64>;
+    // Reports a problem found while evaluating, such as division by zero.
+    fn warn(&mut self, diagnostic: Diagnostic);
+}
+
+// `value` converted to integer type `ty`. Values are kept in this form: sign
+// or zero extended from the width of their type.
+pub fn cast(value: i64, ty: &Type) -> i64 {
+    match ty {
+        Type::Char => value as i8 as i64,
+        Type::UChar => value as u8 as i64,
+        Type::Short => value as i16 as i64,
+        Type::UShort => value as u16 as i64,
+        Type::Int => value as i32 as i64,
+        Type::UInt => value as u32 as i64,
+        _ => value,
+    }
+}
+
+// Whether `expr` is a null pointer constant: an integer constant expression
+// with value 0, possibly cast to `void *` (C11 6.3.2.3).
+pub fn is_null_pointer(expr: &Expression, scope: &mut dyn Scope) -> bool {
+    if matches!(eval(expr, scope), Some((0, _))) {
+        return true;
+    }
+    match expr.as_factor() {
+        Some(Factor::Cast(Type::Pointer(inner), value, _)) if **inner == Type::Void => {
+            matches!(eval_factor(value, scope), Some((0, _)))
+        }
+        _ => false,
+    }
+}
+
+// The value of `expr` and its type.
+pub fn eval(expr: &Expression, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    match expr {
+        Expression::Assign(..) => None,
+        Expression::Conditional(cond) => eval_conditional(cond, scope),
+    }
+}
+
+fn eval_conditional(cond: &ConditionalExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let (value, ty) = eval_log_or(&cond.log_or_expr, scope)?;
+    let Some((a, b)) = &cond.additional else {
+        return Some((value, ty));
+    };
+    // The operand that isn't evaluated may divide by zero, so it only
+    // contributes its type when it has a value.
+    let (taken, other) = if value != 0 {
+        (eval(a, scope)?, eval_conditional(b, scope))
+    } else {
+        (eval_conditional(b, scope)?, eval(a, scope))
+    };
+    let ty = match other {
+        Some((_, other)) => Type::common(&taken.1, &other),
+        None => taken.1.promote(),
+    };
+    Some((cast(taken.0, &ty), ty))
+}
+
+fn eval_log_or(expr: &LogicalOrExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_log_and(&expr.log_and_expr, scope)?;
+    for next in expr.additional.iter() {
+        let result = value.0 != 0 || eval_log_and(next, scope)?.0 != 0;
+        value = (result as i64, Type::Int);
+    }
+    Some(value)
+}
+
+fn eval_log_and(expr: &LogicalAndExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_bit_or(&expr.bit_or_expr, scope)?;
+    for next in expr.additional.iter() {
+        let result = value.0 != 0 && eval_bit_or(next, scope)?.0 != 0;
+        value = (result as i64, Type::Int);
+    }
+    Some(value)
+}
+
+fn eval_bit_or(expr: &BitOrExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_bit_xor(&expr.bit_xor_expr, scope)?;
+    for next in expr.additional.iter() {
+        value = binary(TokenType::BitOr, value, eval_bit_xor(next, scope)?)?;
+    }
+    Some(value)
+}
+
+fn eval_bit_xor(expr: &BitXOrExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_bit_and(&expr.bit_and_expr, scope)?;
+    for next in expr.additional.iter() {
+        value = binary(TokenType::BitXOr, value, eval_bit_and(next, scope)?)?;
+    }
+    Some(value)
+}
+
+fn eval_bit_and(expr: &BitAndExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_equality(&expr.eq_expr, scope)?;
+    for next in expr.additional.iter() {
+        value = binary(TokenType::BitAnd, value, eval_equality(next, scope)?)?;
+    }
+    Some(value)
+}
+
+fn eval_equality(expr: &EqualityExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_relational(&expr.rel_expr, scope)?;
+    for (op, next) in expr.additional.iter() {
+        value = binary(*op, value, eval_relational(next, scope)?)?;
+    }
+    Some(value)
+}
+
+// The value of `expr` and its type, for operands of `==` and `!=`.
+pub fn eval_relational(expr: &RelationalExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_shift(&expr.shift_expr, scope)?;
+    for (op, next) in expr.additional.iter() {
+        value = binary(*op, value, eval_shift(next, scope)?)?;
+    }
+    Some(value)
+}
+
+// The value of `expr` and its type, for operands of relational operators.
+pub fn eval_shift(expr: &ShiftExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_additive(&expr.add_expr, scope)?;
+    for (op, next) in expr.additional.iter() {
+        let rhs = eval_additive(next, scope)?;
+        check_operands(*op, &value.1, &rhs, next.span, scope);
+        value = binary(*op, value, rhs)?;
+    }
+    Some(value)
+}
+
+// The value of `expr` and its type, for operands of `<<` and `>>`.
+pub fn eval_additive(expr: &AdditiveExpr, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_term(&expr.term, scope)?;
+    for (op, next) in expr.additional.iter() {
+        value = binary(*op, value, eval_term(next, scope)?)?;
+    }
+    Some(value)
+}
+
+// The value of `term` and its type, for operands of `+` and `-`.
+pub fn eval_term(term: &Term, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    let mut value = eval_factor(&term.factor, scope)?;
+    for (op, next) in term.additional.iter() {
+        let rhs = eval_factor(next, scope)?;
+        check_operands(*op, &value.1, &rhs, next.span(), scope);
+        value = binary(*op, value, rhs)?;
+    }
+    Some(value)
+}
+
+// The value of `factor` and its type.
+pub fn eval_factor(factor: &Factor, scope: &mut dyn Scope) -> Option<(i64, Type)> {
+    match factor {
+        Factor::Expr(expr, _) => eval(expr, scope),
+        Factor::UnaryOp(op, inner, _) => {
+            let (value, ty) = eval_factor(inner, scope)?;
+            let ty = ty.promote();
+            match op {
+                TokenType::Minus => Some((cast(value.wrapping_neg(), &ty), ty)),
+                TokenType::BitComplement => Some((cast(!value, &ty), ty)),
+                TokenType::LogicalNeg => Some(((value == 0) as i64, Type::Int)),
+                _ => None,
+            }
+        }
+        Factor::Number(literal, _) => {
+            let ty = Type::from(literal.kind);
+            Some((cast(literal.value as i64, &ty), ty))
+        }
+        // Enumerators have type `int`.
+        Factor::Identifier(name, _) => Some((scope.enumerator(name)?, Type::Int)),
+        Factor::Cast(ty, inner, _) => {
+            let ty = scope.resolve(ty);
+            let (value, _) = eval_factor(inner, scope)?;
+            ty.is_integer().then(|| (cast(value, &ty), ty))
+        }
+        // Only the size of a type is known without looking up variables.
+        Factor::SizeOfType(ty, _) => {
+            let ty = scope.resolve(ty);
+            ty.is_complete().then(|| (ty.size() as i64, Type::ULong))
+        }
+        Factor::Postfix(..)
+        | Factor::Index(..)
+        | Factor::Member(..)
+        | Factor::SizeOf(..)
+        | Factor::String(..)
+        | Factor::Call(..) => None,
+    }
+}
+
+// Warns when `rhs`, the constant right operand of `op` at `span`, makes the
+// operation undefined: a zero divisor, or a shift count that is negative or
+// at least the width of the promoted left operand of type `lhs`.
+pub fn check_operands(
+    op: TokenType,
+    lhs: &Type,
+    rhs: &(i64, Type),
+    span: Span,
+    scope: &mut dyn Scope,
+) {
+    if !lhs.is_integer() || !rhs.1.is_integer() {
+        return;
+    }
+    let ty = lhs.promote();
+    let diagnostic = match op {
+        TokenType::Division | TokenType::Modulo if rhs.0 == 0 => {
+            let what = if op == TokenType::Division {
+                "division"
+            } else {
+                "remainder"
+            };
+            Diagnostic::warning(format!("{} by zero is undefined", what), span)
+                .with_code(diagnostics::DIVISION_BY_ZERO)
+                .with_label("the divisor is zero")
+        }
+        TokenType::LBitShift | TokenType::RBitShift if rhs.0 < 0 && !rhs.1.is_unsigned() => {
+            Diagnostic::warning("shift count is negative", span)
+                .with_code(diagnostics::SHIFT_COUNT)
+                .with_label(format!("shifting by {}", rhs.0))
+        }
+        TokenType::LBitShift | TokenType::RBitShift if !in_width(rhs.0, &ty) => {
+            Diagnostic::warning(format!("shift count >= width of type `{}`", ty), span)
+                .with_code(diagnostics::SHIFT_COUNT)
+                .with_label(format!("`{}` is {} bits wide", ty, ty.size() * 8))
+        }
+        _ => return,
+    };
+    scope.warn(diagnostic);
+}
+
+// Whether `count` is a valid shift count for a value of type `ty`.
+fn in_width(count: i64, ty: &Type) -> bool {
+    (0..ty.size() as i64 * 8).contains(&count)
+}
+
+// Applies `op` to two typed values. Both values are first converted to their
+// common type, except for shifts, whose result has the promoted type of the
+// left operand.
+fn binary(op: TokenType, lhs: (i64, Type), rhs: (i64, Type)) -> Option<(i64, Type)> {
+    if matches!(op, TokenType::LBitShift | TokenType::RBitShift) {
+        let (value, ty) = (lhs.0, lhs.1.promote());
+        let count = rhs.0;
+        if !in_width(count, &ty) {
+            return None;
+        }
+        let value = match op {
+            TokenType::LBitShift => value << count,
+            _ if ty.is_unsigned() => ((value as u64) >> count) as i64,
+            _ => value >> count,
+        };
+        return Some((cast(value, &ty), ty));
+    }
+
+    let ty = Type::common(&lhs.1, &rhs.1);
+    let (lhs, rhs) = (cast(lhs.0, &ty), cast(rhs.0, &ty));
+    let unsigned = ty.is_unsigned();
+    let ordering = if unsigned {
+        (lhs as u64).cmp(&(rhs as u64))
+    } else {
+        lhs.cmp(&rhs)
+    };
+    let value = match op {
+        TokenType::Addition => lhs.wrapping_add(rhs),
+        TokenType::Minus => lhs.wrapping_sub(rhs),
+        TokenType::Multiplication => lhs.wrapping_mul(rhs),
+        TokenType::Division if unsigned => (lhs as u64).checked_div(rhs as u64)? as i64,
+        TokenType::Division => lhs.checked_div(rhs)?,
+        TokenType::Modulo if unsigned => (lhs as u64).checked_rem(rhs as u64)? as i64,
+        TokenType::Modulo => lhs.checked_rem(rhs)?,
+        TokenType::BitAnd => lhs & rhs,
+        TokenType::BitOr => lhs | rhs,
+        TokenType::BitXOr => lhs ^ rhs,
+        TokenType::Equal => return Some((ordering.is_eq() as i64, Type::Int)),
+        TokenType::NotEqual => return Some((ordering.is_ne() as i64, Type::Int)),
+        TokenType::LessThan => return Some((ordering.is_lt() as i64, Type::Int)),
+        TokenType::LessThanEqual => return Some((ordering.is_le() as i64, Type::Int)),
+        TokenType::GreaterThan => return Some((ordering.is_gt() as i64, Type::Int)),
+        TokenType::GreaterThanEqual => return Some((ordering.is_ge() as i64, Type::Int)),
+        _ => return None,
+    };
+    Some((cast(value, &ty), ty))
+}
@@ -0,0 +1,707 @@
+use crate::span::{SourceMap, Span};
+use std::fmt::Write;
+use std::io::IsTerminal;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+// Stable identifiers for each kind of diagnostic, so tools can match on them
+// without parsing messages.
+pub const SYNTAX_ERROR: &str = "E0001";
+pub const UNDECLARED_IDENTIFIER: &str = "E0002";
+pub const REDECLARATION: &str = "E0003";
+pub const UNTERMINATED_COMMENT: &str = "E0004";
+pub const UNKNOWN_CHARACTER: &str = "E0005";
+pub const INVALID_LITERAL: &str = "E0006";
+pub const LITERAL_TOO_LARGE: &str = "E0007";
+pub const UNTERMINATED_LITERAL: &str = "E0008";
+pub const INVALID_ESCAPE: &str = "E0009";
+pub const NOT_ASSIGNABLE: &str = "E0010";
+pub const JUMP_OUTSIDE_LOOP: &str = "E0011";
+pub const CASE_OUTSIDE_SWITCH: &str = "E0012";
+pub const DUPLICATE_CASE: &str = "E0013";
+pub const NOT_CONSTANT: &str = "E0014";
+pub const UNDEFINED_LABEL: &str = "E0015";
+pub const DUPLICATE_LABEL: &str = "E0016";
+pub const UNDECLARED_FUNCTION: &str = "E0017";
+pub const CONFLICTING_TYPES: &str = "E0018";
+pub const ARGUMENT_COUNT: &str = "E0019";
+pub const CONFLICTING_LINKAGE: &str = "E0020";
+pub const INVALID_INITIALIZER: &str = "E0021";
+pub const INVALID_OPERANDS: &str = "E0022";
+pub const INCOMPATIBLE_TYPES: &str = "E0023";
+pub const INVALID_ARRAY_SIZE: &str = "E0024";
+pub const INCOMPLETE_TYPE: &str = "E0025";
+pub const NO_MEMBER: &str = "E0026";
+pub const ENUMERATOR_RANGE: &str = "E0027";
+pub const RETURN_VALUE: &str = "E0028";
+
+pub const NESTED_COMMENT: &str = "W0001";
+pub const IMPLICITLY_UNSIGNED: &str = "W0002";
+pub const UNKNOWN_ESCAPE: &str = "W0003";
+pub const MULTI_CHAR_CONSTANT: &str = "W0004";
+pub const IMPLICIT_DECLARATION: &str = "W0005";
+pub const INCOMPATIBLE_POINTER_TYPES: &str = "W0006";
+pub const DIVISION_BY_ZERO: &str = "W0007";
+pub const SHIFT_COUNT: &str = "W0008";
+pub const POINTER_INTEGER_COMPARISON: &str = "W0009";
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Format {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+// A machine-applicable fix: replace the text under `span` with `replacement`.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub span: Span,
+    pub replacement: String,
+    pub message: String,
+}
+
+// A message about the source, pointing at `span`. `label` is printed next to
+// the primary caret, `secondary` labels point at related locations.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub span: Span,
+    pub label: Option<String>,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+    pub suggestion: Option<Suggestion>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code: None,
+            message: message.into(),
+            span,
+            label: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            suggestion: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message, span)
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, message, span)
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Diagnostic {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_suggestion(
+        mut self,
+        span: Span,
+        replacement: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Diagnostic {
+        self.suggestion = Some(Suggestion {
+            span,
+            replacement: replacement.into(),
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Diagnostic {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.secondary.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    // Renders the diagnostic rustc-style:
+    //
+    // error: expected `;` after expression
+    //  --> file.c:3:9
+    //   |
+    // 3 |   x = 3 y = 4;
+    //   |         ^ found identifier `y`
+    pub fn render(&self, sources: &SourceMap, color: bool) -> String {
+        let style = Style { color };
+        let mut out = String::new();
+
+        let severity_style = match self.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => CYAN,
+        };
+        let name = match self.code {
+            Some(code) => format!("{}[{}]", self.severity.name(), code),
+            None => self.severity.name().to_string(),
+        };
+
+        let width = std::iter::once(self.span.line)
+            .chain(self.secondary.iter().map(|l| l.span.line))
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+        let pad = " ".repeat(width);
+
+        let _ = writeln!(
+            out,
+            "{}{}",
+            style.paint(severity_style, &format!("{}:", name)),
+            style.paint(BOLD, &format!(" {}", self.message))
+        );
+        let _ = writeln!(
+            out,
+            "{}{} {}:{}:{}",
+            pad,
+            style.paint(BLUE, "-->"),
+            sources.get(self.span.file).name,
+            self.span.line,
+            self.span.col
+        );
+        let _ = writeln!(out, "{} {}", pad, style.paint(BLUE, "|"));
+
+        // Labels on the same line share one copy of it, with the primary
+        // line first.
+        let marks: Vec<Mark> = std::iter::once(Mark {
+            span: self.span,
+            mark: '^',
+            style: severity_style,
+            label: self.label.as_deref(),
+        })
+        .chain(self.secondary.iter().map(|label| Mark {
+            span: label.span,
+            mark: '-',
+            style: BLUE,
+            label: Some(&label.message),
+        }))
+        .collect();
+        let snippets = Snippets {
+            sources,
+            style: &style,
+            width,
+        };
+        let mut lines: Vec<(usize, u32)> = Vec::new();
+        for mark in marks.iter() {
+            if !lines.contains(&(mark.span.file, mark.span.line)) {
+                lines.push((mark.span.file, mark.span.line));
+            }
+        }
+        for (file, line) in lines {
+            let on_line: Vec<&Mark> = marks
+                .iter()
+                .filter(|mark| (mark.span.file, mark.span.line) == (file, line))
+                .collect();
+            snippets.write(&mut out, &on_line);
+        }
+
+        if let Some(suggestion) = &self.suggestion {
+            let _ = writeln!(
+                out,
+                "{} {} {} `{}`",
+                pad,
+                style.paint(BLUE, "="),
+                style.paint(BOLD, "help:") + " " + &suggestion.message,
+                suggestion.replacement
+            );
+        }
+
+        for note in self.notes.iter() {
+            let _ = writeln!(
+                out,
+                "{} {} {}",
+                pad,
+                style.paint(BLUE, "="),
+                style.paint(BOLD, "note:") + " " + note
+            );
+        }
+
+        out
+    }
+}
+
+impl Severity {
+    fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+impl Diagnostic {
+    // One JSON object on a single line, e.g.
+    // {"severity":"error","code":"E0001","message":"...","span":{...},...}
+    pub fn to_json(&self, sources: &SourceMap) -> String {
+        let span = |span: Span| {
+            format!(
+                "{{\"file\":{},\"line\":{},\"column\":{},\"start\":{},\"end\":{}}}",
+                json_string(&sources.get(span.file).name),
+                span.line,
+                span.col,
+                span.start,
+                span.end
+            )
+        };
+        let optional = |value: Option<&str>| value.map_or(String::from("null"), json_string);
+
+        let labels: Vec<String> = self
+            .secondary
+            .iter()
+            .map(|label| {
+                format!(
+                    "{{\"span\":{},\"message\":{}}}",
+                    span(label.span),
+                    json_string(&label.message)
+                )
+            })
+            .collect();
+        let notes: Vec<String> = self.notes.iter().map(|n| json_string(n)).collect();
+        let suggestion = match &self.suggestion {
+            Some(s) => format!(
+                "{{\"span\":{},\"replacement\":{},\"message\":{}}}",
+                span(s.span),
+                json_string(&s.replacement),
+                json_string(&s.message)
+            ),
+            None => String::from("null"),
+        };
+
+        format!(
+            "{{\"severity\":{},\"code\":{},\"message\":{},\"span\":{},\"label\":{},\"labels\":[{}],\"notes\":[{}],\"suggestion\":{}}}",
+            json_string(self.severity.name()),
+            optional(self.code),
+            json_string(&self.message),
+            span(self.span),
+            optional(self.label.as_deref()),
+            labels.join(","),
+            notes.join(","),
+            suggestion
+        )
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+const RESET: &str = "\x1b[0m";
+
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// An underlined span in a snippet, with its label.
+struct Mark<'a> {
+    span: Span,
+    mark: char,
+    style: &'static str,
+    label: Option<&'a str>,
+}
+
+struct Snippets<'a> {
+    sources: &'a SourceMap,
+    style: &'a Style,
+    width: usize,
+}
+
+impl Snippets<'_> {
+    // Prints the source line containing the `marks` once and underlines each
+    // of them. The rightmost label goes after its underline, the others below
+    // it, rustc-style:
+    //
+    // 3 |   x = 3 y = 4;
+    //   |   -     ^ found identifier `y`
+    //   |   |
+    //   |   previous assignment
+    fn write(&self, out: &mut String, marks: &[&Mark]) {
+        let (style, width) = (self.style, self.width);
+        let file = self.sources.get(marks[0].span.file);
+        let number = marks[0].span.line;
+        let line: Vec<char> = file.line(number).chars().collect();
+
+        let _ = writeln!(
+            out,
+            "{:>width$} {} {}",
+            style.paint(BLUE, &number.to_string()),
+            style.paint(BLUE, "|"),
+            line.iter().collect::<String>(),
+            width = if style.color {
+                width + BLUE.len() + RESET.len()
+            } else {
+                width
+            }
+        );
+
+        // Each column of the underline, drawn with the primary mark last so it
+        // wins where marks overlap.
+        let mut cells: Vec<Option<(char, &str)>> = Vec::new();
+        for mark in marks.iter().rev() {
+            let col = mark.span.col as usize - 1;
+            let len = file.text[mark.span.start..mark.span.end]
+                .chars()
+                .count()
+                .min(line.len().saturating_sub(col))
+                .max(1);
+            if cells.len() < col + len {
+                cells.resize(col + len, None);
+            }
+            for cell in cells[col..col + len].iter_mut() {
+                *cell = Some((mark.mark, mark.style));
+            }
+        }
+
+        let mut labelled: Vec<&Mark> = marks
+            .iter()
+            .copied()
+            .filter(|mark| mark.label.is_some())
+            .collect();
+        labelled.sort_by_key(|mark| mark.span.col);
+        let inline = labelled.pop();
+
+        let mut pieces: Vec<(usize, String, &str)> = Vec::new();
+        for (col, cell) in cells.iter().enumerate() {
+            let Some((mark, mark_style)) = *cell else {
+                continue;
+            };
+            match pieces.last_mut() {
+                Some((start, text, last_style))
+                    if *last_style == mark_style && *start + text.chars().count() == col =>
+                {
+                    text.push(mark);
+                }
+                _ => pieces.push((col, mark.to_string(), mark_style)),
+            }
+        }
+        if let Some(mark) = inline {
+            pieces.push((cells.len() + 1, mark.label.unwrap().to_string(), mark.style));
+        }
+        self.write_row(out, &line, &pieces);
+
+        // The other labels, rightmost first, each hanging from its mark.
+        while let Some(mark) = labelled.pop() {
+            let col = mark.span.col as usize - 1;
+            let mut pieces: Vec<(usize, String, &str)> = labelled
+                .iter()
+                .chain(std::iter::once(&mark))
+                .map(|m| (m.span.col as usize - 1, String::from("|"), m.style))
+                .collect();
+            self.write_row(out, &line, &pieces);
+            pieces.pop();
+            pieces.push((col, mark.label.unwrap().to_string(), mark.style));
+            self.write_row(out, &line, &pieces);
+        }
+    }
+
+    // Prints one row under the source line, with each piece's text starting
+    // at its column. Tabs in the source are kept so the columns line up.
+    fn write_row(&self, out: &mut String, line: &[char], pieces: &[(usize, String, &str)]) {
+        let style = self.style;
+        let blank = |i: usize| match line.get(i) {
+            Some('\t') => '\t',
+            _ => ' ',
+        };
+        let mut row = String::new();
+        let mut at = 0;
+        for (col, text, piece_style) in pieces.iter() {
+            row.extend((at..*col).map(blank));
+            row.push_str(&style.paint(piece_style, text));
+            at = (*col).max(at) + text.chars().count();
+        }
+        let _ = writeln!(
+            out,
+            "{} {} {}",
+            " ".repeat(self.width),
+            style.paint(BLUE, "|"),
+            row
+        );
+    }
+}
+
+// Prints diagnostics to stderr, in colour when stderr is a terminal. JSON
+// output is one object per line.
+pub fn emit(diagnostics: &[Diagnostic], sources: &SourceMap, format: Format) {
+    let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    for diagnostic in diagnostics.iter() {
+        match format {
+            Format::Human => eprintln!("{}", diagnostic.render(sources, color)),
+            Format::Json => eprintln!("{}", diagnostic.to_json(sources)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(text: &str) -> SourceMap {
+        let mut sources = SourceMap::default();
+        sources.add("test.c", text.to_string());
+        sources
+    }
+
+    // The span of `len` bytes starting at the 1-based `line` and `col`.
+    fn span(text: &str, line: u32, col: u32, len: usize) -> Span {
+        let start = text
+            .split_inclusive('\n')
+            .take(line as usize - 1)
+            .map(str::len)
+            .sum::<usize>()
+            + col as usize
+            - 1;
+        Span {
+            file: 0,
+            start,
+            end: start + len,
+            line,
+            col,
+        }
+    }
+
+    #[test]
+    fn renders_the_header_location_and_caret() {
+        let text = "int main(void) {\n  x = 3 y = 4;\n}\n";
+        let diagnostic = Diagnostic::error("expected `;` after expression", span(text, 2, 9, 1))
+            .with_code(SYNTAX_ERROR)
+            .with_label("found identifier `y`");
+        assert_eq!(
+            diagnostic.render(&sources(text), false),
+            "\
+error[E0001]: expected `;` after expression
+ --> test.c:2:9
+  |
+2 |   x = 3 y = 4;
+  |         ^ found identifier `y`
+"
+        );
+
+        let diagnostic = Diagnostic::warning("unused value", span(text, 2, 3, 5));
+        assert_eq!(
+            diagnostic.render(&sources(text), false),
+            "\
+warning: unused value
+ --> test.c:2:3
+  |
+2 |   x = 3 y = 4;
+  |   ^^^^^
+"
+        );
+    }
+
+    #[test]
+    fn widens_the_gutter_for_the_largest_line_number() {
+        let text = "int f(int a, int b);\n\n\n\n\n\n\n\n\nint f(int a);\n";
+        let diagnostic = Diagnostic::error("conflicting types for `f`", span(text, 10, 1, 13))
+            .with_label("declared here")
+            .with_secondary(span(text, 1, 1, 20), "previously declared here");
+        assert_eq!(
+            diagnostic.render(&sources(text), false),
+            "\
+error: conflicting types for `f`
+  --> test.c:10:1
+   |
+10 | int f(int a);
+   | ^^^^^^^^^^^^^ declared here
+ 1 | int f(int a, int b);
+   | -------------------- previously declared here
+"
+        );
+    }
+
+    #[test]
+    fn keeps_tabs_and_stops_the_underline_at_the_end_of_the_line() {
+        let text = "\tint x = /* open\n";
+        let diagnostic = Diagnostic::error("unterminated block comment", span(text, 1, 10, 8))
+            .with_label("comment starts here");
+        assert_eq!(
+            diagnostic.render(&sources(text), false),
+            "\
+error: unterminated block comment
+ --> test.c:1:10
+  |
+1 | \tint x = /* open
+  | \t        ^^^^^^^ comment starts here
+"
+        );
+    }
+
+    #[test]
+    fn merges_labels_on_the_same_line() {
+        let text = "int b = 1; int b = 2;\n";
+        let diagnostic = Diagnostic::error("redefinition of `b`", span(text, 1, 12, 10))
+            .with_label("redefined here")
+            .with_secondary(span(text, 1, 1, 10), "previous definition is here");
+        assert_eq!(
+            diagnostic.render(&sources(text), false),
+            "\
+error: redefinition of `b`
+ --> test.c:1:12
+  |
+1 | int b = 1; int b = 2;
+  | ---------- ^^^^^^^^^^ redefined here
+  | |
+  | previous definition is here
+"
+        );
+
+        // With the secondary label to the right, it goes inline instead, and
+        // every other label hangs below its own mark.
+        let text = "f(a, b, c);\n";
+        let diagnostic = Diagnostic::error("bad call", span(text, 1, 1, 1))
+            .with_label("called here")
+            .with_secondary(span(text, 1, 3, 1), "first")
+            .with_secondary(span(text, 1, 9, 1), "third")
+            .with_secondary(span(text, 1, 6, 1), "second");
+        assert_eq!(
+            diagnostic.render(&sources(text), false),
+            "\
+error: bad call
+ --> test.c:1:1
+  |
+1 | f(a, b, c);
+  | ^ -  -  - third
+  | | |  |
+  | | |  second
+  | | |
+  | | first
+  | |
+  | called here
+"
+        );
+    }
+
+    #[test]
+    fn renders_help_and_notes() {
+        let text = "int main(void) { return 0 }\n";
+        let diagnostic = Diagnostic::error("expected `;`", span(text, 1, 27, 1))
+            .with_label("expected `;` here")
+            .with_suggestion(span(text, 1, 26, 0), ";", "insert a semicolon")
+            .with_note("a return statement ends with `;`")
+            .with_note("see C11 6.8.6");
+        assert_eq!(
+            diagnostic.render(&sources(text), false),
+            "\
+error: expected `;`
+ --> test.c:1:27
+  |
+1 | int main(void) { return 0 }
+  |                           ^ expected `;` here
+  = help: insert a semicolon `;`
+  = note: a return statement ends with `;`
+  = note: see C11 6.8.6
+"
+        );
+    }
+
+    #[test]
+    fn serializes_every_field_as_json() {
+        let text = "int b = 1; int b = 2;\n";
+        let diagnostic = Diagnostic::error("redefinition of \"b\"", span(text, 1, 12, 10))
+            .with_code(REDECLARATION)
+            .with_label("path C:\\src\tb")
+            .with_secondary(span(text, 1, 1, 10), "previous\ndefinition")
+            .with_note("bell \x07 and \r")
+            .with_note("second")
+            .with_suggestion(span(text, 1, 12, 4), "", "remove `int`");
+        assert_eq!(
+            diagnostic.to_json(&sources(text)),
+            concat!(
+                r#"{"severity":"error","code":"E0003","message":"redefinition of \"b\"","#,
+                r#""span":{"file":"test.c","line":1,"column":12,"start":11,"end":21},"#,
+                r#""label":"path C:\\src\tb","#,
+                r#""labels":[{"span":{"file":"test.c","line":1,"column":1,"start":0,"end":10},"#,
+                r#""message":"previous\ndefinition"}],"#,
+                r#""notes":["bell \u0007 and \r","second"],"#,
+                r#""suggestion":{"span":{"file":"test.c","line":1,"column":12,"start":11,"end":15},"#,
+                r#""replacement":"","message":"remove `int`"}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn serializes_missing_fields_as_null_or_empty() {
+        let text = "x\n";
+        let diagnostic = Diagnostic::warning("unused", span(text, 1, 1, 1));
+        assert_eq!(
+            diagnostic.to_json(&sources(text)),
+            concat!(
+                r#"{"severity":"warning","code":null,"message":"unused","#,
+                r#""span":{"file":"test.c","line":1,"column":1,"start":0,"end":1},"#,
+                r#""label":null,"labels":[],"notes":[],"suggestion":null}"#
+            )
+        );
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        assert_eq!(json_string(""), r#""""#);
+        assert_eq!(json_string("a\"b\\c"), r#""a\"b\\c""#);
+        assert_eq!(json_string("\n\r\t"), r#""\n\r\t""#);
+        assert_eq!(json_string("\0\x1b\x1f "), r#""\u0000\u001b\u001f ""#);
+        assert_eq!(json_string("é/\x7f"), "\"é/\x7f\"");
+    }
+}
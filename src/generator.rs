@@ -1,361 +1,3632 @@
-use crate::lexer::TokenType;
+use crate::constant;
+use crate::diagnostics::{self, Diagnostic};
+use crate::lexer::{IntKind, TokenType};
 use crate::parser::*;
-use std::collections::HashMap;
+use crate::span::Span;
+use crate::types::{Aggregate, Type};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::slice::Iter;
+
+// Registers for the first six integer arguments, per the System V AMD64 ABI.
+const ARG_REGISTERS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+
+// How a value is passed or returned under the System V AMD64 ABI (3.2.3):
+// in general-purpose registers, one for each of its eightbytes, or in memory.
+// Only floating-point members make an eightbyte SSE class, which would use
+// vector registers, and crust has no floating-point types.
+#[derive(PartialEq)]
+enum Class {
+    Integer(usize),
+    Memory,
+}
+
+// Structs larger than two eightbytes are passed in memory; anything else
+// crust has fits in as many registers as it has eightbytes.
+fn classify(ty: &Type) -> Class {
+    match ty.size() {
+        size if size > 16 => Class::Memory,
+        size => Class::Integer((size as usize).div_ceil(8)),
+    }
+}
+
+// The registers each argument of a call is passed in, or None for those
+// passed on the stack. An argument goes on the stack whole if there are too
+// few registers left for it, though later ones may still fit. A function
+// returning in memory takes the address to return into as a hidden first
+// argument.
+fn assign_registers(args: &[Type], return_type: &Type) -> Vec<Option<&'static [&'static str]>> {
+    let mut next = match classify(return_type) {
+        Class::Memory => 1,
+        Class::Integer(_) => 0,
+    };
+    args.iter()
+        .map(|ty| match classify(ty) {
+            Class::Integer(count) if next + count <= ARG_REGISTERS.len() => {
+                next += count;
+                Some(&ARG_REGISTERS[next - count..next])
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// The language standard being compiled against, from `-std`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Standard {
+    C89,
+    C99,
+    C11,
+    C17,
+}
+
+// What the declarations of a function seen so far say about it.
+struct FunctionInfo {
+    return_type: Type,
+    // Parameter types, or None if no declaration has a prototype: it was
+    // declared with `()` or implicitly by a call.
+    params: Option<Vec<Type>>,
+    // The declaration `params` comes from, and the definition.
+    span: Span,
+    defined: Option<Span>,
+    // Declared `static`, so not visible to other translation units.
+    internal: bool,
+    // Only declared implicitly by a call, so even the return type is a guess.
+    implicit: bool,
+}
+
+// What the file-scope declarations of a variable seen so far say about it.
+struct GlobalInfo {
+    ty: Type,
+    span: Span,
+    init: Vec<(i32, Type, StaticValue)>,
+    // The declaration with an initializer, if any.
+    defined: Option<Span>,
+    // Declared without `extern`, so defined (as 0) even without an
+    // initializer.
+    tentative: bool,
+    internal: bool,
+}
+
+// A variable with static storage duration, emitted into .data or .bss.
+struct StaticData {
+    label: String,
+    ty: Type,
+    // The scalars given a value, as (offset, type, value); the rest is zero.
+    init: Vec<(i32, Type, StaticValue)>,
+    global: bool,
+}
+
+// The value of a scalar with static storage duration: a number, or an
+// address for the linker to fill in, as a label and an offset from it.
+#[derive(Clone, PartialEq)]
+enum StaticValue {
+    Integer(i64),
+    Address(String, i64),
+}
+
+impl fmt::Display for StaticValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StaticValue::Integer(value) => write!(f, "{}", value),
+            StaticValue::Address(label, 0) => write!(f, "{}", label),
+            StaticValue::Address(label, offset) => write!(f, "{}{:+}", label, offset),
+        }
+    }
+}
+
+// A scalar inside an object being initialized: either an expression, or a
+// character of a string literal initializing a `char` array.
+enum Scalar<'a> {
+    Expr(&'a Expression),
+    Constant(i64),
+}
+
+// What an ordinary identifier in scope names.
+#[derive(Clone)]
+enum Symbol {
+    // A variable: where it lives (a memory operand such as `-8(%rbp)` or
+    // `x(%rip)`) and its type.
+    Object(String, Type),
+    // An enumerator, which is an `int` constant.
+    Constant(i64),
+}
+
+// What a struct, union or enum tag is declared as. Enums are only declared
+// by their definition, and have type `int`.
+#[derive(Clone)]
+enum Tag {
+    Aggregate(Rc<Aggregate>),
+    Enum,
+}
 
 pub struct StackInfo {
     counter: u32,
     stack_index: i32,
-    var_map: HashMap<String, i32>,
+    // The variables and enumerators declared in each enclosing block, with
+    // where they were declared, file scope first and the innermost block
+    // last.
+    scopes: Vec<HashMap<String, (Symbol, Span)>>,
+    // The tags declared in each of those blocks, and the type each specifier
+    // in the program resolved to.
+    tags: Vec<HashMap<String, (Tag, Span)>>,
+    specifiers: HashMap<*const TagSpecifier, Type>,
+    diagnostics: Vec<Diagnostic>,
+    // String literals, emitted into .rodata as `.LC<index>`.
+    strings: Vec<Vec<u8>>,
+    // Where `break` and `continue` jump to in the innermost enclosing loop,
+    // with the stack index to unwind to first.
+    breaks: Vec<(String, i32)>,
+    continues: Vec<(String, i32)>,
+    // Labels of the `case`s and `default` in the innermost switch not yet
+    // generated, in source order.
+    switches: Vec<VecDeque<String>>,
+    // Switch jump tables, emitted into .rodata.
+    jump_tables: String,
+    // The function being generated, its return type and the labels declared
+    // in it. A function returning in memory saves the address to return into
+    // at `return_pointer`.
+    function: String,
+    return_type: Type,
+    return_pointer: Option<String>,
+    labels: HashMap<String, Span>,
+    // The frame slot of each call in the function that returns a struct,
+    // reserved by the prologue so the value can outlive the call.
+    return_slots: HashMap<*const Factor, i32>,
+    // Every function and file-scope variable declared so far, the latter in
+    // declaration order.
+    functions: HashMap<String, FunctionInfo>,
+    globals: HashMap<String, GlobalInfo>,
+    global_order: Vec<String>,
+    // Function-local `static` variables.
+    data: Vec<StaticData>,
+    standard: Standard,
+}
+
+impl StackInfo {
+    // Pushes %rax, keeping `stack_index` in step with %rsp so calls can
+    // align the stack.
+    fn push(&mut self, text: &mut String) {
+        text.push_str("push %rax\n");
+        self.stack_index -= 8;
+    }
+
+    fn pop(&mut self, text: &mut String, register: &str) {
+        text.push_str(format!("pop %{}\n", register).as_str());
+        self.stack_index += 8;
+    }
+
+    // Reserves stack space for an array or struct of `size` bytes, in whole
+    // 8-byte slots, returning its offset from %rbp.
+    fn allocate(&mut self, text: &mut String, size: i32) -> i32 {
+        let slots = (size + 7) / 8 * 8;
+        if slots > 0 {
+            text.push_str(format!("subq ${}, %rsp\n", slots).as_str());
+            self.stack_index -= slots;
+        }
+        self.stack_index
+    }
+
+    // Pops everything pushed or allocated since `stack_index`, such as the
+    // temporaries holding structs returned by calls.
+    fn release(&mut self, text: &mut String, stack_index: i32) {
+        if self.stack_index != stack_index {
+            text.push_str(format!("addq ${}, %rsp\n", stack_index - self.stack_index).as_str());
+            self.stack_index = stack_index;
+        }
+    }
+
+    // Reports `name` if it is already declared in the innermost scope.
+    fn redeclared(&mut self, name: &str, span: Span) -> bool {
+        let Some((_, previous)) = self.scopes.last().unwrap().get(name) else {
+            return false;
+        };
+        let diagnostic = Diagnostic::error(
+            format!("`{}` is already declared in this scope", name),
+            span,
+        )
+        .with_code(diagnostics::REDECLARATION)
+        .with_label("redeclared here")
+        .with_secondary(*previous, "previous declaration is here");
+        self.diagnostics.push(diagnostic);
+        true
+    }
+
+    fn declare(&mut self, name: &str, operand: String, ty: Type, span: Span) {
+        let scope = self.scopes.last_mut().unwrap();
+        scope.insert(name.to_string(), (Symbol::Object(operand, ty), span));
+    }
+
+    fn enter_scope(&mut self) -> i32 {
+        self.scopes.push(HashMap::new());
+        self.tags.push(HashMap::new());
+        self.stack_index
+    }
+
+    // Pops the innermost scope, releasing the stack slots of its variables.
+    fn exit_scope(&mut self, text: &mut String, stack_index: i32) {
+        self.scopes.pop();
+        self.tags.pop();
+        self.release(text, stack_index);
+    }
+
+    // A fresh number for uniquely naming the labels of one construct.
+    fn label(&mut self) -> u32 {
+        self.counter += 1;
+        self.counter - 1
+    }
+
+    fn lookup(&mut self, name: &str, span: Span) -> Option<Symbol> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some((symbol, _)) => Some(symbol.clone()),
+            None => {
+                self.diagnostics.push(
+                    Diagnostic::error(format!("use of undeclared identifier `{}`", name), span)
+                        .with_code(diagnostics::UNDECLARED_IDENTIFIER)
+                        .with_label("not found in this scope"),
+                );
+                None
+            }
+        }
+    }
+}
+
+impl constant::Scope for StackInfo {
+    fn resolve(&mut self, ty: &Type) -> Type {
+        resolve(ty, self)
+    }
+
+    fn enumerator(&mut self, name: &str) -> Option<i64> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some((Symbol::Constant(value), _)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    // An operand may be evaluated more than once, for code generation and as
+    // part of a constant, so each warning is only reported the first time.
+    fn warn(&mut self, diagnostic: Diagnostic) {
+        let seen = self
+            .diagnostics
+            .iter()
+            .any(|d| d.span == diagnostic.span && d.code == diagnostic.code);
+        if !seen {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+}
+
+// Values live in %rax. Those narrower than `int` are kept sign or zero
+// extended to 32 bits, so arithmetic only ever happens on 32 or 64 bits; the
+// upper half of %rax is garbage for 32-bit values. Arrays and structs are
+// represented by their address.
+
+// The operand size suffix for arithmetic on `ty`.
+fn suffix(ty: &Type) -> &'static str {
+    if ty.size() == 8 {
+        "q"
+    } else {
+        "l"
+    }
+}
+
+fn rax(ty: &Type) -> &'static str {
+    if ty.size() == 8 {
+        "%rax"
+    } else {
+        "%eax"
+    }
+}
+
+fn rcx(ty: &Type) -> &'static str {
+    if ty.size() == 8 {
+        "%rcx"
+    } else {
+        "%ecx"
+    }
+}
+
+// The operand size suffix for accessing an object of type `ty` in memory.
+fn size_suffix(ty: &Type) -> &'static str {
+    match ty.size() {
+        1 => "b",
+        2 => "w",
+        4 => "l",
+        _ => "q",
+    }
+}
+
+// Loads the object of type `ty` at `operand` into %rax, or just its address
+// if it is an array or struct.
+fn load(text: &mut String, ty: &Type, operand: &str) {
+    let instruction = match ty {
+        Type::Array(..) | Type::Struct(_) => String::from("leaq"),
+        Type::Char => String::from("movsbl"),
+        Type::UChar => String::from("movzbl"),
+        Type::Short => String::from("movswl"),
+        Type::UShort => String::from("movzwl"),
+        _ => format!("mov{}", suffix(ty)),
+    };
+    let register = if ty.is_scalar() { rax(ty) } else { "%rax" };
+    text.push_str(format!("{} {}, {}\n", instruction, operand, register).as_str());
+}
+
+// Stores %rax into the object of type `ty` at `operand`. A struct is copied
+// from the address in %rax, which is left holding the address of the copy.
+fn store(text: &mut String, ty: &Type, operand: &str) {
+    if let Type::Struct(_) = ty {
+        text.push_str(
+            format!(
+                "leaq {}, %rdi\nmovq %rax, %rsi\nmovq %rdi, %rax\nmovq ${}, %rcx\nrep movsb\n",
+                operand,
+                ty.size()
+            )
+            .as_str(),
+        );
+        return;
+    }
+    let register = match ty.size() {
+        1 => "%al",
+        2 => "%ax",
+        4 => "%eax",
+        _ => "%rax",
+    };
+    text.push_str(format!("mov{} {}, {}\n", size_suffix(ty), register, operand).as_str());
+}
+
+// Converts the value in %rax from type `from` to type `to`.
+fn convert(text: &mut String, from: &Type, to: &Type) {
+    if to.size() == 8 && from.size() < 8 {
+        // Writing a 32-bit register zeroes the upper half.
+        text.push_str(if from.is_unsigned() {
+            "movl %eax, %eax\n"
+        } else {
+            "cltq\n"
+        });
+    } else if to.size() < 4 && from != to {
+        let extend = match to {
+            Type::Char => "movsbl %al, %eax\n",
+            Type::UChar => "movzbl %al, %eax\n",
+            Type::Short => "movswl %ax, %eax\n",
+            _ => "movzwl %ax, %eax\n",
+        };
+        text.push_str(extend);
+    }
+    // Otherwise the low bits already hold the converted value.
 }
 
-pub fn generate_factor(text: &mut String, factor: &Factor, stack_info: &mut StackInfo) {
-    // <factor> ::= "(" <exp> ")" | <unary_op> <factor> | <int>
+// Sets the flags for testing the value in %rax against zero.
+fn compare_zero(text: &mut String, ty: &Type) {
+    text.push_str(format!("cmp{} $0, {}\n", suffix(ty), rax(ty)).as_str());
+}
+
+pub fn generate_factor(text: &mut String, factor: &Factor, stack_info: &mut StackInfo) -> Type {
+    // <factor> ::= "(" <exp> ")" | <unary_op> <factor> | <factor> <postfix_op> | <int>
+    generate_object(text, factor, stack_info).decay()
+}
+
+// Like `generate_factor`, but leaves arrays as arrays (with their address in
+// %rax), for `sizeof`.
+fn generate_object(text: &mut String, factor: &Factor, stack_info: &mut StackInfo) -> Type {
     match &factor {
-        Factor::Expr(boxed_expr) => {
-            generate_expr(text, boxed_expr, stack_info);
+        Factor::Expr(boxed_expr, _) => match boxed_expr.as_factor() {
+            Some(inner) => generate_object(text, inner, stack_info),
+            None => generate_expr(text, boxed_expr, stack_info),
+        },
+        // ++x / --x: update the variable, then load the new value.
+        Factor::UnaryOp(op @ (TokenType::Increment | TokenType::Decrement), target, span) => {
+            let Some((operand, ty)) = generate_assignable(text, target, stack_info) else {
+                return Type::Int;
+            };
+            if !check_scalar(&ty, *span, stack_info) {
+                return ty;
+            }
+            generate_step(text, *op, &ty, &operand);
+            load(text, &ty, &operand);
+            ty
+        }
+        // x++ / x--: load the old value, then update the variable.
+        Factor::Postfix(op, target, span) => {
+            let Some((operand, ty)) = generate_assignable(text, target, stack_info) else {
+                return Type::Int;
+            };
+            if !check_scalar(&ty, *span, stack_info) {
+                return ty;
+            }
+            load(text, &ty, &operand);
+            generate_step(text, *op, &ty, &operand);
+            ty
+        }
+        Factor::UnaryOp(TokenType::BitAnd, target, _) => {
+            let Some((operand, ty)) = generate_lvalue(text, target, stack_info) else {
+                return Type::Int;
+            };
+            text.push_str(format!("leaq {}, %rax\n", operand).as_str());
+            Type::Pointer(Box::new(ty))
         }
-        Factor::UnaryOp(op, boxed_factor) => {
-            generate_factor(text, boxed_factor, stack_info);
+        Factor::UnaryOp(TokenType::Multiplication, inner, span) => {
+            let ty = generate_factor(text, inner, stack_info);
+            match dereference(&ty, *span, stack_info) {
+                Some(pointee) => {
+                    load(text, &pointee, "(%rax)");
+                    pointee
+                }
+                None => Type::Int,
+            }
+        }
+        Factor::UnaryOp(op, boxed_factor, span) => {
+            let ty = generate_factor(text, boxed_factor, stack_info).promote();
+            if !ty.is_scalar() || (ty.is_pointer() && *op != TokenType::LogicalNeg) {
+                stack_info.diagnostics.push(
+                    Diagnostic::error(
+                        format!(
+                            "invalid argument type `{}` to unary `{}`",
+                            ty,
+                            operator(*op)
+                        ),
+                        *span,
+                    )
+                    .with_code(diagnostics::INVALID_OPERANDS),
+                );
+                return ty;
+            }
             match op {
-                TokenType::Minus => text.push_str("neg %eax\n"),
-                TokenType::BitComplement => text.push_str("not %eax\n"),
-                TokenType::LogicalNeg => text.push_str("cmpl $0, %eax\nmovl $0, %eax\nsete %al\n"),
+                TokenType::Minus => {
+                    text.push_str(format!("neg{} {}\n", suffix(&ty), rax(&ty)).as_str());
+                    ty
+                }
+                TokenType::BitComplement => {
+                    text.push_str(format!("not{} {}\n", suffix(&ty), rax(&ty)).as_str());
+                    ty
+                }
+                TokenType::LogicalNeg => {
+                    compare_zero(text, &ty);
+                    text.push_str("movl $0, %eax\nsete %al\n");
+                    Type::Int
+                }
                 _ => {
                     dbg!(op);
                     panic!();
                 }
             }
-        }
-        Factor::Number(val) => {
-            text.push_str(format!("movl ${}, %eax\n", val).as_str());
-        }
-        Factor::Identifier(name) => {
-            let offset = stack_info.var_map.get(name).unwrap();
-            text.push_str(format!("movl {}(%ebp), %eax\n", offset).as_str());
-        }
+        }
+        Factor::Number(literal, _) => {
+            match literal.kind {
+                IntKind::Int | IntKind::UInt => {
+                    text.push_str(format!("movl ${}, %eax\n", literal.value).as_str());
+                }
+                _ if literal.value <= i32::MAX as u64 => {
+                    text.push_str(format!("movq ${}, %rax\n", literal.value).as_str());
+                }
+                _ => text.push_str(format!("movabsq ${}, %rax\n", literal.value).as_str()),
+            }
+            Type::from(literal.kind)
+        }
+        Factor::String(bytes, _) => {
+            let label = stack_info.strings.len();
+            stack_info.strings.push(bytes.clone());
+            text.push_str(format!("leaq .LC{}(%rip), %rax\n", label).as_str());
+            // An array of the characters and the terminating null, which
+            // decays to a pointer like any other.
+            Type::Array(Box::new(Type::Char), bytes.len() + 1)
+        }
+        Factor::Index(base, index, span) => {
+            match generate_element(text, base, index, *span, stack_info) {
+                Some(ty) => {
+                    load(text, &ty, "(%rax)");
+                    ty
+                }
+                None => Type::Int,
+            }
+        }
+        // The operand is only generated for its type.
+        Factor::SizeOf(inner, span) => {
+            let stack_index = stack_info.stack_index;
+            let ty = generate_object(&mut String::new(), inner, stack_info);
+            stack_info.stack_index = stack_index;
+            generate_size(text, &ty, *span, stack_info)
+        }
+        Factor::SizeOfType(ty, span) => {
+            let ty = resolve(ty, stack_info);
+            generate_size(text, &ty, *span, stack_info)
+        }
+        Factor::Cast(ty, inner, span) => {
+            let ty = resolve(ty, stack_info);
+            let from = generate_factor(text, inner, stack_info);
+            if ty != Type::Void && check_scalar(&ty, *span, stack_info) {
+                check_scalar(&from, inner.span(), stack_info);
+            }
+            convert(text, &from, &ty);
+            ty
+        }
+        Factor::Member(op, base, name, span) => {
+            match generate_member(text, *op, base, name, *span, stack_info) {
+                Some(ty) => {
+                    load(text, &ty, "(%rax)");
+                    ty
+                }
+                None => Type::Int,
+            }
+        }
+        Factor::Identifier(name, span) => match stack_info.lookup(name, *span) {
+            Some(Symbol::Object(operand, ty)) => {
+                load(text, &ty, &operand);
+                ty
+            }
+            Some(Symbol::Constant(value)) => {
+                text.push_str(format!("movl ${}, %eax\n", value).as_str());
+                Type::Int
+            }
+            None => Type::Int,
+        },
+        Factor::Call(name, args, span) => {
+            let slot = stack_info
+                .return_slots
+                .get(&(factor as *const Factor))
+                .copied();
+            generate_call(text, name, args, slot, *span, stack_info)
+        }
+    }
+}
+
+// Loads the size of an object of type `ty` as an `unsigned long`.
+fn generate_size(text: &mut String, ty: &Type, span: Span, stack_info: &mut StackInfo) -> Type {
+    if !ty.is_complete() && *ty != Type::Void {
+        stack_info.diagnostics.push(
+            Diagnostic::error(
+                format!(
+                    "invalid application of `sizeof` to an incomplete type `{}`",
+                    ty
+                ),
+                span,
+            )
+            .with_code(diagnostics::INCOMPLETE_TYPE)
+            .with_label("size not known here"),
+        );
+    }
+    text.push_str(format!("movq ${}, %rax\n", ty.size()).as_str());
+    Type::ULong
+}
+
+// Moves the struct of type `ty` at the address in %rax to where the caller
+// expects it: into %rax and %rdx, or copied to the address it passed, which
+// is also returned.
+fn generate_struct_return(text: &mut String, ty: &Type, stack_info: &mut StackInfo) {
+    if ty.is_scalar() || *ty == Type::Void {
+        return;
+    }
+    match (classify(ty), &stack_info.return_pointer) {
+        (Class::Memory, Some(pointer)) => {
+            text.push_str(format!("movq {}, %rdi\n", pointer).as_str());
+            store(text, ty, "(%rdi)");
+        }
+        (Class::Integer(count), _) => {
+            // Copied into whole eightbytes first, so as not to read past the
+            // end of the struct.
+            let stack_index = stack_info.stack_index;
+            let temporary = stack_info.allocate(text, ty.size());
+            store(text, ty, &format!("{}(%rbp)", temporary));
+            if count > 1 {
+                text.push_str("movq 8(%rax), %rdx\n");
+            }
+            text.push_str("movq (%rax), %rax\n");
+            // The function returns right after, so the stack isn't unwound.
+            stack_info.stack_index = stack_index;
+        }
+        (Class::Memory, None) => unreachable!("functions returning in memory save the pointer"),
+    }
+}
+
+// Reports a value of type `ty` used where only a number or pointer will do,
+// such as a condition. Returns whether it is one.
+fn check_scalar(ty: &Type, span: Span, stack_info: &mut StackInfo) -> bool {
+    if ty.is_scalar() {
+        return true;
+    }
+    stack_info.diagnostics.push(
+        Diagnostic::error(
+            format!("`{}` used where a number or pointer is required", ty),
+            span,
+        )
+        .with_code(diagnostics::INVALID_OPERANDS)
+        .with_label("not a scalar"),
+    );
+    false
+}
+
+// Computes the address of the member `name` of the struct or union `base`,
+// or for `->` of the one it points to, into %rax. Returns the member's type.
+fn generate_member(
+    text: &mut String,
+    op: TokenType,
+    base: &Factor,
+    name: &str,
+    span: Span,
+    stack_info: &mut StackInfo,
+) -> Option<Type> {
+    let ty = generate_factor(text, base, stack_info);
+    let target = match op {
+        TokenType::Arrow => ty.pointee(),
+        _ => Some(&ty),
+    };
+    let Some(Type::Struct(aggregate)) = target else {
+        let message = match op {
+            TokenType::Arrow if !ty.is_pointer() => {
+                format!("member reference type `{}` is not a pointer", ty)
+            }
+            _ => format!(
+                "member reference base type `{}` is not a structure or union",
+                target.unwrap_or(&ty)
+            ),
+        };
+        stack_info.diagnostics.push(
+            Diagnostic::error(message, base.span())
+                .with_code(diagnostics::INVALID_OPERANDS)
+                .with_label(format!("has type `{}`", ty)),
+        );
+        return None;
+    };
+    if !aggregate.is_complete() {
+        stack_info.diagnostics.push(
+            Diagnostic::error(
+                format!("incomplete definition of type `{}`", aggregate),
+                span,
+            )
+            .with_code(diagnostics::INCOMPLETE_TYPE)
+            .with_label(format!("`{}` is declared but not defined", aggregate)),
+        );
+        return None;
+    }
+    let Some((_, member)) = aggregate.find(name) else {
+        stack_info.diagnostics.push(
+            Diagnostic::error(
+                format!("no member named `{}` in `{}`", name, aggregate),
+                span,
+            )
+            .with_code(diagnostics::NO_MEMBER)
+            .with_label("unknown member"),
+        );
+        return None;
+    };
+    if member.offset != 0 {
+        text.push_str(format!("addq ${}, %rax\n", member.offset).as_str());
+    }
+    Some(member.ty)
+}
+
+// Checks a call against the declaration of the function. Before C99, calling
+// an undeclared function implicitly declares it.
+fn check_call(name: &str, args: usize, span: Span, stack_info: &mut StackInfo) {
+    match stack_info.functions.get(name) {
+        Some(FunctionInfo {
+            params: Some(params),
+            span: declared,
+            ..
+        }) if params.len() != args => {
+            let message = if args > params.len() {
+                format!("too many arguments to function `{}`", name)
+            } else {
+                format!("too few arguments to function `{}`", name)
+            };
+            let diagnostic = Diagnostic::error(message, span)
+                .with_code(diagnostics::ARGUMENT_COUNT)
+                .with_label(format!(
+                    "expected {}, found {}",
+                    plural(params.len(), "argument"),
+                    args
+                ))
+                .with_secondary(*declared, format!("`{}` declared here", name));
+            stack_info.diagnostics.push(diagnostic);
+        }
+        Some(_) => {}
+        None if stack_info.standard == Standard::C89 => {
+            stack_info.diagnostics.push(
+                Diagnostic::warning(format!("implicit declaration of function `{}`", name), span)
+                    .with_code(diagnostics::IMPLICIT_DECLARATION)
+                    .with_label("assumed to return `int`"),
+            );
+            let info = FunctionInfo {
+                return_type: Type::Int,
+                params: None,
+                span,
+                defined: None,
+                internal: false,
+                implicit: true,
+            };
+            stack_info.functions.insert(name.to_string(), info);
+        }
+        None => stack_info.diagnostics.push(
+            Diagnostic::error(format!("call to undeclared function `{}`", name), span)
+                .with_code(diagnostics::UNDECLARED_FUNCTION)
+                .with_label("not declared before this call")
+                .with_note("ISO C99 and later do not support implicit function declarations"),
+        ),
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("1 {}", word)
+    } else {
+        format!("{} {}s", count, word)
+    }
+}
+
+// Calls `name`, passing the first six arguments in registers and the rest on
+// the stack, right to left. Arguments are converted to the parameter types,
+// or just promoted if the function has no prototype. A struct is returned
+// into the frame slot `slot`.
+fn generate_call(
+    text: &mut String,
+    name: &str,
+    args: &[Expression],
+    slot: Option<i32>,
+    span: Span,
+    stack_info: &mut StackInfo,
+) -> Type {
+    check_call(name, args.len(), span, stack_info);
+    let (return_type, params) = match stack_info.functions.get(name) {
+        Some(info) => (info.return_type.clone(), info.params.clone()),
+        None => (Type::Int, None),
+    };
+
+    let buffer = (!return_type.is_scalar() && return_type != Type::Void)
+        .then(|| slot.expect("the prologue reserves a slot for each struct return"));
+    let stack_index = stack_info.stack_index;
+
+    // Where each argument goes depends on the types of those before it, so
+    // they are all evaluated into temporaries first.
+    let mut temporaries = VecDeque::new();
+    for (i, arg) in args.iter().enumerate().rev() {
+        let from = generate_expr(text, arg, stack_info);
+        let ty = match params.as_ref().and_then(|params| params.get(i)) {
+            Some(param) => {
+                check_conversion(&from, param, arg, stack_info);
+                param.clone()
+            }
+            None => from.promote(),
+        };
+        convert(text, &from, &ty);
+        let temporary = if ty.is_scalar() {
+            stack_info.push(text);
+            stack_info.stack_index
+        } else {
+            let temporary = stack_info.allocate(text, ty.size());
+            store(text, &ty, &format!("{}(%rbp)", temporary));
+            temporary
+        };
+        temporaries.push_front((ty, temporary));
+    }
+    let types: Vec<Type> = temporaries.iter().map(|(ty, _)| ty.clone()).collect();
+    let registers = assign_registers(&types, &return_type);
+
+    // %rsp must be 16-byte aligned at the call, once stack arguments are
+    // pushed.
+    let on_stack: i32 = temporaries
+        .iter()
+        .zip(registers.iter())
+        .filter(|(_, registers)| registers.is_none())
+        .map(|((ty, _), _)| (ty.size() + 7) / 8 * 8)
+        .sum();
+    let padding = (stack_info.stack_index - on_stack).rem_euclid(16);
+    if padding != 0 {
+        text.push_str(format!("subq ${}, %rsp\n", padding).as_str());
+        stack_info.stack_index -= padding;
+    }
+    for ((ty, temporary), _) in temporaries
+        .iter()
+        .zip(registers.iter())
+        .rev()
+        .filter(|(_, registers)| registers.is_none())
+    {
+        for eightbyte in (0..(ty.size() + 7) / 8).rev() {
+            text.push_str(format!("pushq {}(%rbp)\n", temporary + 8 * eightbyte).as_str());
+            stack_info.stack_index -= 8;
+        }
+    }
+    for ((_, temporary), registers) in temporaries.iter().zip(registers.iter()) {
+        for (eightbyte, register) in registers.iter().flat_map(|r| r.iter()).enumerate() {
+            text.push_str(
+                format!(
+                    "movq {}(%rbp), %{}\n",
+                    temporary + 8 * eightbyte as i32,
+                    register
+                )
+                .as_str(),
+            );
+        }
+    }
+    if let (Some(buffer), Class::Memory) = (buffer, classify(&return_type)) {
+        text.push_str(format!("leaq {}(%rbp), %rdi\n", buffer).as_str());
+    }
+
+    // Variadic functions read the number of vector registers used from %al.
+    text.push_str(format!("movl $0, %eax\ncall {}\n", name).as_str());
+    stack_info.release(text, stack_index);
+
+    match (buffer, classify(&return_type)) {
+        // The callee returns the address it was given.
+        (Some(_), Class::Memory) => {}
+        (Some(buffer), Class::Integer(count)) => {
+            for (eightbyte, register) in ["rax", "rdx"].iter().take(count).enumerate() {
+                text.push_str(
+                    format!(
+                        "movq %{}, {}(%rbp)\n",
+                        register,
+                        buffer + 8 * eightbyte as i32
+                    )
+                    .as_str(),
+                );
+            }
+            text.push_str(format!("leaq {}(%rbp), %rax\n", buffer).as_str());
+        }
+        // The callee need not extend return values narrower than `int`.
+        (None, _) if return_type.size() < 4 => convert(text, &Type::Int, &return_type),
+        (None, _) => {}
+    }
+    return_type
+}
+
+// Applies `++` or `--` to the object of type `ty` at `operand`. Pointers
+// step by the size of what they point to.
+fn generate_step(text: &mut String, op: TokenType, ty: &Type, operand: &str) {
+    let instruction = if op == TokenType::Increment {
+        "add"
+    } else {
+        "sub"
+    };
+    let amount = ty.pointee().map_or(1, Type::size);
+    text.push_str(
+        format!(
+            "{}{} ${}, {}\n",
+            instruction,
+            size_suffix(ty),
+            amount,
+            operand
+        )
+        .as_str(),
+    );
+}
+
+// Computes the address of `base[index]` into %rax, returning the element
+// type.
+fn generate_element(
+    text: &mut String,
+    base: &Factor,
+    index: &Expression,
+    span: Span,
+    stack_info: &mut StackInfo,
+) -> Option<Type> {
+    let base = generate_factor(text, base, stack_info);
+    let ty = generate_binary(
+        text,
+        base,
+        TokenType::Addition,
+        span,
+        stack_info,
+        |text, stack_info| generate_expr(text, index, stack_info),
+    );
+    if !ty.is_pointer() {
+        stack_info.diagnostics.push(
+            Diagnostic::error("subscripted value is not an array or pointer", span)
+                .with_code(diagnostics::INVALID_OPERANDS)
+                .with_label(format!("has type `{}`", ty)),
+        );
+        return None;
+    }
+    dereference(&ty, span, stack_info)
+}
+
+// The type `*` yields for an operand of type `ty`, reporting operands that
+// can't be dereferenced.
+fn dereference(ty: &Type, span: Span, stack_info: &mut StackInfo) -> Option<Type> {
+    let message = match ty.pointee() {
+        Some(Type::Void) => format!("dereferencing `{}` pointer", ty),
+        Some(pointee) => return Some(pointee.clone()),
+        None => format!("indirection requires pointer operand (`{}` invalid)", ty),
+    };
+    stack_info.diagnostics.push(
+        Diagnostic::error(message, span)
+            .with_code(diagnostics::INVALID_OPERANDS)
+            .with_label("cannot dereference this"),
+    );
+    None
+}
+
+// How an operator is written in C, for diagnostics.
+fn operator(op: TokenType) -> &'static str {
+    match op {
+        TokenType::Addition => "+",
+        TokenType::Minus => "-",
+        TokenType::Multiplication => "*",
+        TokenType::Division => "/",
+        TokenType::Modulo => "%",
+        TokenType::LBitShift => "<<",
+        TokenType::RBitShift => ">>",
+        TokenType::BitAnd => "&",
+        TokenType::BitOr => "|",
+        TokenType::BitXOr => "^",
+        TokenType::BitComplement => "~",
+        TokenType::Equal => "==",
+        TokenType::NotEqual => "!=",
+        TokenType::LessThan => "<",
+        TokenType::LessThanEqual => "<=",
+        TokenType::GreaterThan => ">",
+        TokenType::GreaterThanEqual => ">=",
+        _ => "?",
+    }
+}
+
+// The types the operands of a binary operator are converted to: their
+// common type, or for shifts each one promoted. An integer added to or
+// subtracted from a pointer becomes a `long` to be scaled, and an integer
+// compared with a pointer becomes a pointer. None if the operator doesn't
+// apply to these operands.
+fn operand_types(op: TokenType, lhs: &Type, rhs: &Type) -> Option<(Type, Type)> {
+    let comparison = matches!(
+        op,
+        TokenType::Equal
+            | TokenType::NotEqual
+            | TokenType::LessThan
+            | TokenType::LessThanEqual
+            | TokenType::GreaterThan
+            | TokenType::GreaterThanEqual
+    );
+    if !lhs.is_scalar() || !rhs.is_scalar() {
+        return None;
+    }
+    match (lhs.is_pointer(), rhs.is_pointer()) {
+        (false, false) => match op {
+            TokenType::LBitShift | TokenType::RBitShift => Some((lhs.promote(), rhs.promote())),
+            _ => Some((Type::common(lhs, rhs), Type::common(lhs, rhs))),
+        },
+        (true, true) if comparison => Some((lhs.clone(), rhs.clone())),
+        (true, true) if op == TokenType::Minus && lhs == rhs => Some((lhs.clone(), rhs.clone())),
+        (true, false) if comparison => Some((lhs.clone(), lhs.clone())),
+        (false, true) if comparison => Some((rhs.clone(), rhs.clone())),
+        (true, false) if matches!(op, TokenType::Addition | TokenType::Minus) => {
+            Some((lhs.clone(), Type::Long))
+        }
+        (false, true) if op == TokenType::Addition => Some((Type::Long, rhs.clone())),
+        _ => None,
+    }
+}
+
+// Converts the operand of type `from` in %rax to `ty` for use alongside an
+// operand of type `other`, scaling integers added to pointers.
+fn prepare_operand(text: &mut String, from: &Type, ty: &Type, other: &Type) {
+    convert(text, from, ty);
+    if let (false, Some(pointee)) = (ty.is_pointer(), other.pointee()) {
+        if pointee.size() != 1 {
+            text.push_str(format!("imulq ${}, %rax\n", pointee.size()).as_str());
+        }
+    }
+}
+
+// Applies `op` to prepared operands of types `lhs` in %rax and `rhs` in
+// %rcx, returning the type of the result.
+fn apply_binary_op(text: &mut String, op: TokenType, lhs: &Type, rhs: &Type) -> Type {
+    match (lhs.pointee(), rhs.is_pointer()) {
+        // The difference of two pointers counts elements, as a ptrdiff_t.
+        (Some(pointee), true) if op == TokenType::Minus => {
+            text.push_str("subq %rcx, %rax\n");
+            if pointee.size() != 1 {
+                text.push_str(
+                    format!("movq ${}, %rcx\ncqto\nidivq %rcx\n", pointee.size()).as_str(),
+                );
+            }
+            Type::Long
+        }
+        (None, true) => generate_binary_op(text, op, rhs),
+        _ => generate_binary_op(text, op, lhs),
+    }
+}
+
+fn invalid_operands(op: TokenType, lhs: &Type, rhs: &Type, span: Span, stack_info: &mut StackInfo) {
+    stack_info.diagnostics.push(
+        Diagnostic::error(
+            format!(
+                "invalid operands to binary `{}` (`{}` and `{}`)",
+                operator(op),
+                lhs,
+                rhs
+            ),
+            span,
+        )
+        .with_code(diagnostics::INVALID_OPERANDS),
+    );
+}
+
+// Applies a binary operator working in type `ty`, with the left operand in
+// %rax and the right operand in %rcx, leaving the result in %rax. Returns
+// the type of the result.
+fn generate_binary_op(text: &mut String, op: TokenType, ty: &Type) -> Type {
+    let (s, a, c) = (suffix(ty), rax(ty), rcx(ty));
+    let condition = match op {
+        TokenType::Addition => {
+            text.push_str(format!("add{} {}, {}\n", s, c, a).as_str());
+            return ty.clone();
+        }
+        TokenType::Minus => {
+            text.push_str(format!("sub{} {}, {}\n", s, c, a).as_str());
+            return ty.clone();
+        }
+        TokenType::Multiplication => {
+            text.push_str(format!("imul{} {}, {}\n", s, c, a).as_str());
+            return ty.clone();
+        }
+        // The dividend is [edx:eax] (or [rdx:rax]): sign extended for idiv,
+        // zeroed for div. The quotient ends up in eax and the remainder in
+        // edx.
+        TokenType::Division | TokenType::Modulo => {
+            if ty.is_unsigned() {
+                text.push_str(format!("xorl %edx, %edx\ndiv{} {}\n", s, c).as_str());
+            } else {
+                let extend = if ty.size() == 8 { "cqto" } else { "cltd" };
+                text.push_str(format!("{}\nidiv{} {}\n", extend, s, c).as_str());
+            }
+            if op == TokenType::Modulo {
+                let d = if ty.size() == 8 { "%rdx" } else { "%edx" };
+                text.push_str(format!("mov{} {}, {}\n", s, d, a).as_str());
+            }
+            return ty.clone();
+        }
+        TokenType::LBitShift => {
+            text.push_str(format!("sal{} %cl, {}\n", s, a).as_str());
+            return ty.clone();
+        }
+        TokenType::RBitShift => {
+            let shift = if ty.is_unsigned() { "shr" } else { "sar" };
+            text.push_str(format!("{}{} %cl, {}\n", shift, s, a).as_str());
+            return ty.clone();
+        }
+        TokenType::BitAnd | TokenType::BitOr | TokenType::BitXOr => {
+            let instruction = match op {
+                TokenType::BitAnd => "and",
+                TokenType::BitOr => "or",
+                _ => "xor",
+            };
+            text.push_str(format!("{}{} {}, {}\n", instruction, s, c, a).as_str());
+            return ty.clone();
+        }
+        TokenType::Equal => "e",
+        TokenType::NotEqual => "ne",
+        // Unsigned comparisons test the carry flag: below and above.
+        TokenType::LessThan if ty.is_unsigned() => "b",
+        TokenType::LessThanEqual if ty.is_unsigned() => "be",
+        TokenType::GreaterThan if ty.is_unsigned() => "a",
+        TokenType::GreaterThanEqual if ty.is_unsigned() => "ae",
+        TokenType::LessThan => "l",
+        TokenType::LessThanEqual => "le",
+        TokenType::GreaterThan => "g",
+        TokenType::GreaterThanEqual => "ge",
+        _ => {
+            dbg!(op);
+            panic!();
+        }
+    };
+    text.push_str(
+        format!(
+            "cmp{} {}, {}\nset{} %al\nmovzbl %al, %eax\n",
+            s, c, a, condition
+        )
+        .as_str(),
+    );
+    Type::Int
+}
+
+// Applies `op` to the value of type `lhs` in %rax and the operand `rhs`
+// generates, converting both to the types the operation works in. `span`
+// covers the whole expression.
+fn generate_binary<F>(
+    text: &mut String,
+    lhs: Type,
+    op: TokenType,
+    span: Span,
+    stack_info: &mut StackInfo,
+    rhs: F,
+) -> Type
+where
+    F: FnOnce(&mut String, &mut StackInfo) -> Type,
+{
+    stack_info.push(text);
+    let rhs = rhs(text, stack_info);
+    let Some((lhs_ty, rhs_ty)) = operand_types(op, &lhs, &rhs) else {
+        invalid_operands(op, &lhs, &rhs, span, stack_info);
+        stack_info.pop(text, "rcx");
+        // Carry on with the left operand's type to avoid follow-on errors.
+        return lhs;
+    };
+    prepare_operand(text, &rhs, &rhs_ty, &lhs_ty);
+    text.push_str("movq %rax, %rcx\n");
+    stack_info.pop(text, "rax");
+    prepare_operand(text, &lhs, &lhs_ty, &rhs_ty);
+    apply_binary_op(text, op, &lhs_ty, &rhs_ty)
+}
+
+pub fn generate_term(text: &mut String, term: &Term, stack_info: &mut StackInfo) -> Type {
+    // <term> ::= <factor> { ("*" | "/" | "%") <factor> }
+    let mut ty = generate_factor(text, &term.factor, stack_info);
+
+    for (op, factor) in term.additional.iter() {
+        if let Some(rhs) = constant::eval_factor(factor, stack_info) {
+            constant::check_operands(*op, &ty, &rhs, factor.span(), stack_info);
+        }
+        ty = generate_binary(text, ty, *op, term.span, stack_info, |text, stack_info| {
+            generate_factor(text, factor, stack_info)
+        });
+    }
+    ty
+}
+
+pub fn generate_add_expr(
+    text: &mut String,
+    add_expr: &AdditiveExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <add-expr> ::= <term> { ("+" | "-") <term> }
+    let mut ty = generate_term(text, &add_expr.term, stack_info);
+
+    for (op, expr) in add_expr.additional.iter() {
+        ty = generate_binary(
+            text,
+            ty,
+            *op,
+            add_expr.span,
+            stack_info,
+            |text, stack_info| generate_term(text, expr, stack_info),
+        );
+    }
+    ty
+}
+
+pub fn generate_shift_expr(
+    text: &mut String,
+    shift_expr: &ShiftExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <shift-expr> ::= <add-expr> { ("<<" | ">>") <add-expr> }
+    let mut ty = generate_add_expr(text, &shift_expr.add_expr, stack_info);
+
+    for (op, expr) in shift_expr.additional.iter() {
+        if let Some(rhs) = constant::eval_additive(expr, stack_info) {
+            constant::check_operands(*op, &ty, &rhs, expr.span, stack_info);
+        }
+        ty = generate_binary(
+            text,
+            ty,
+            *op,
+            shift_expr.span,
+            stack_info,
+            |text, stack_info| generate_add_expr(text, expr, stack_info),
+        );
+    }
+    ty
+}
+
+pub fn generate_rel_expr(
+    text: &mut String,
+    rel_expr: &RelationalExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <rel-expr> ::= <shift-expr> { ("<" | ">" | "<=" | ">=") <shift-expr> }
+    let mut ty = generate_shift_expr(text, &rel_expr.shift_expr, stack_info);
+    let first = &rel_expr.shift_expr;
+    let mut lhs_zero = !rel_expr.additional.is_empty()
+        && matches!(constant::eval_shift(first, stack_info), Some((0, _)));
+    let mut lhs_span = first.span;
+
+    for (op, expr) in rel_expr.additional.iter() {
+        let lhs = (ty.clone(), lhs_span, lhs_zero);
+        ty = generate_binary(
+            text,
+            ty,
+            *op,
+            rel_expr.span,
+            stack_info,
+            |text, stack_info| {
+                let rhs_zero = matches!(constant::eval_shift(expr, stack_info), Some((0, _)));
+                let rhs = generate_shift_expr(text, expr, stack_info);
+                check_comparison(&lhs, (&rhs, expr.span, rhs_zero), stack_info);
+                rhs
+            },
+        );
+        lhs_span = lhs_span.to(expr.span);
+        lhs_zero = false;
+    }
+    ty
+}
+
+pub fn generate_eq_expr(
+    text: &mut String,
+    eq_expr: &EqualityExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <eq-expr> ::= <rel-expr> { ("!=" | "==") <rel-expr> }
+    let mut ty = generate_rel_expr(text, &eq_expr.rel_expr, stack_info);
+    let first = &eq_expr.rel_expr;
+    let mut lhs_zero = !eq_expr.additional.is_empty()
+        && matches!(constant::eval_relational(first, stack_info), Some((0, _)));
+    let mut lhs_span = first.span;
+
+    for (op, expr) in eq_expr.additional.iter() {
+        let lhs = (ty.clone(), lhs_span, lhs_zero);
+        ty = generate_binary(
+            text,
+            ty,
+            *op,
+            eq_expr.span,
+            stack_info,
+            |text, stack_info| {
+                let rhs_zero = matches!(constant::eval_relational(expr, stack_info), Some((0, _)));
+                let rhs = generate_rel_expr(text, expr, stack_info);
+                check_comparison(&lhs, (&rhs, expr.span, rhs_zero), stack_info);
+                rhs
+            },
+        );
+        lhs_span = lhs_span.to(expr.span);
+        lhs_zero = false;
+    }
+    ty
+}
+
+// Warns when a pointer is compared with an integer that isn't a null pointer
+// constant. Each operand comes with its span and whether it is a constant 0.
+fn check_comparison(
+    lhs: &(Type, Span, bool),
+    rhs: (&Type, Span, bool),
+    stack_info: &mut StackInfo,
+) {
+    let (span, zero) = match (lhs.0.is_pointer(), rhs.0.is_pointer()) {
+        (true, false) if rhs.0.is_integer() => (rhs.1, rhs.2),
+        (false, true) if lhs.0.is_integer() => (lhs.1, lhs.2),
+        _ => return,
+    };
+    if zero {
+        return;
+    }
+    stack_info.diagnostics.push(
+        Diagnostic::warning(
+            format!(
+                "comparison between pointer and integer (`{}` and `{}`)",
+                lhs.0, rhs.0
+            ),
+            span,
+        )
+        .with_code(diagnostics::POINTER_INTEGER_COMPARISON)
+        .with_label("not a null pointer constant"),
+    );
+}
+
+pub fn generate_bit_and_expr(
+    text: &mut String,
+    bit_and_expr: &BitAndExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <bit-and-expr> ::= <eq-expr> { "&" <eq-expr> }
+    let mut ty = generate_eq_expr(text, &bit_and_expr.eq_expr, stack_info);
+
+    for expr in bit_and_expr.additional.iter() {
+        ty = generate_binary(
+            text,
+            ty,
+            TokenType::BitAnd,
+            bit_and_expr.span,
+            stack_info,
+            |text, stack_info| generate_eq_expr(text, expr, stack_info),
+        );
+    }
+    ty
+}
+
+pub fn generate_bit_xor_expr(
+    text: &mut String,
+    bit_xor_expr: &BitXOrExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <bit-xor-expr> ::= <bit-and-expr> { "^" <bit-and-expr> }
+    let mut ty = generate_bit_and_expr(text, &bit_xor_expr.bit_and_expr, stack_info);
+
+    for expr in bit_xor_expr.additional.iter() {
+        ty = generate_binary(
+            text,
+            ty,
+            TokenType::BitXOr,
+            bit_xor_expr.span,
+            stack_info,
+            |text, stack_info| generate_bit_and_expr(text, expr, stack_info),
+        );
+    }
+    ty
+}
+
+pub fn generate_bit_or_expr(
+    text: &mut String,
+    bit_or_expr: &BitOrExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <bit-or-expr> ::= <bit-xor-expr> { "|" <bit-xor-expr> }
+    let mut ty = generate_bit_xor_expr(text, &bit_or_expr.bit_xor_expr, stack_info);
+
+    for expr in bit_or_expr.additional.iter() {
+        ty = generate_binary(
+            text,
+            ty,
+            TokenType::BitOr,
+            bit_or_expr.span,
+            stack_info,
+            |text, stack_info| generate_bit_xor_expr(text, expr, stack_info),
+        );
+    }
+    ty
+}
+
+pub fn generate_log_and_expr(
+    text: &mut String,
+    log_and_expr: &LogicalAndExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <log-and-expr> ::= <bit-or-expr> { "&&" <bit-or-expr> }
+    let mut ty = generate_bit_or_expr(text, &log_and_expr.bit_or_expr, stack_info);
+    if !log_and_expr.additional.is_empty() {
+        check_scalar(&ty, log_and_expr.bit_or_expr.span, stack_info);
+    }
+
+    for expr in log_and_expr.additional.iter() {
+        let c = stack_info.label();
+        compare_zero(text, &ty);
+        text.push_str(format!("jne _clause{}\njmp _end{}\n_clause{}:\n", c, c, c).as_str());
+        let rhs = generate_bit_or_expr(text, expr, stack_info);
+        check_scalar(&rhs, expr.span, stack_info);
+        compare_zero(text, &rhs);
+        text.push_str(format!("movl $0, %eax\nsetne %al\n_end{}:\n", c).as_str());
+        ty = Type::Int;
+    }
+    ty
+}
+
+pub fn generate_log_or_expr(
+    text: &mut String,
+    log_or_expr: &LogicalOrExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <logical-or-expr> ::= <logical-and-expr> { "||" <logical-and-expr> }
+    let mut ty = generate_log_and_expr(text, &log_or_expr.log_and_expr, stack_info);
+    if !log_or_expr.additional.is_empty() {
+        check_scalar(&ty, log_or_expr.log_and_expr.span, stack_info);
+    }
+
+    for expr in log_or_expr.additional.iter() {
+        let c = stack_info.label();
+        compare_zero(text, &ty);
+        text.push_str(
+            format!(
+                "je _clause{}\nmovl $1, %eax\njmp _end{}\n_clause{}:\n",
+                c, c, c
+            )
+            .as_str(),
+        );
+        let rhs = generate_log_and_expr(text, expr, stack_info);
+        check_scalar(&rhs, expr.span, stack_info);
+        compare_zero(text, &rhs);
+        text.push_str(format!("movl $0, %eax\nsetne %al\n_end{}:\n", c).as_str());
+        ty = Type::Int;
+    }
+    ty
+}
+
+pub fn generate_conditional_expr(
+    text: &mut String,
+    conditional_expr: &ConditionalExpr,
+    stack_info: &mut StackInfo,
+) -> Type {
+    // <conditional-expr> ::= <logical-or-expr> { "?" <expr> ":" <conditional-expr> }
+    let ty = generate_log_or_expr(text, &conditional_expr.log_or_expr, stack_info);
+
+    let Some((a, b)) = &conditional_expr.additional else {
+        return ty;
+    };
+    check_scalar(&ty, conditional_expr.log_or_expr.span, stack_info);
+    let c = stack_info.label();
+    compare_zero(text, &ty);
+    text.push_str(format!("je _e{}\n", c).as_str());
+
+    // Both branches are converted to their common type, which is only known
+    // once both are generated.
+    let mut then_text = String::new();
+    let then_ty = generate_expr(&mut then_text, a, stack_info);
+    let mut else_text = String::new();
+    let else_ty = generate_conditional_expr(&mut else_text, b, stack_info);
+    if (!then_ty.is_scalar() || !else_ty.is_scalar()) && then_ty != else_ty {
+        stack_info.diagnostics.push(
+            Diagnostic::error(
+                format!(
+                    "incompatible operand types (`{}` and `{}`)",
+                    then_ty, else_ty
+                ),
+                conditional_expr.span,
+            )
+            .with_code(diagnostics::INCOMPATIBLE_TYPES)
+            .with_label("branches must have compatible types"),
+        );
+    }
+    let ty = Type::common(&then_ty, &else_ty);
+
+    text.push_str(&then_text);
+    convert(text, &then_ty, &ty);
+    text.push_str(format!("jmp _post_cond{}\n_e{}:\n", c, c).as_str());
+    text.push_str(&else_text);
+    convert(text, &else_ty, &ty);
+    text.push_str(format!("_post_cond{}:\n", c).as_str());
+    ty
+}
+
+// The binary operator applied by a compound assignment, or None for plain `=`.
+fn compound_op(op: TokenType) -> Option<TokenType> {
+    match op {
+        TokenType::AssignPlus => Some(TokenType::Addition),
+        TokenType::AssignMinus => Some(TokenType::Minus),
+        TokenType::AssignMult => Some(TokenType::Multiplication),
+        TokenType::AssignDivide => Some(TokenType::Division),
+        TokenType::AssignMod => Some(TokenType::Modulo),
+        TokenType::AssignLBitShift => Some(TokenType::LBitShift),
+        TokenType::AssignRBitShift => Some(TokenType::RBitShift),
+        TokenType::AssignBitAnd => Some(TokenType::BitAnd),
+        TokenType::AssignBitOr => Some(TokenType::BitOr),
+        TokenType::AssignBitXOr => Some(TokenType::BitXOr),
+        _ => None,
+    }
+}
+
+// Resolves an lvalue (the operand of `&`, or the target of an assignment) to
+// a memory operand and its type. Only variables, dereferenced pointers and
+// subscripts (possibly parenthesized) are lvalues; the address of the latter
+// two is computed into %rdi.
+fn generate_lvalue(
+    text: &mut String,
+    target: &Factor,
+    stack_info: &mut StackInfo,
+) -> Option<(String, Type)> {
+    match target {
+        Factor::Identifier(name, span) => match stack_info.lookup(name, *span)? {
+            Symbol::Object(operand, ty) => Some((operand, ty)),
+            Symbol::Constant(_) => not_assignable(*span, stack_info),
+        },
+        Factor::UnaryOp(TokenType::Multiplication, inner, span) => {
+            let ty = generate_factor(text, inner, stack_info);
+            let pointee = dereference(&ty, *span, stack_info)?;
+            text.push_str("movq %rax, %rdi\n");
+            Some((String::from("(%rdi)"), pointee))
+        }
+        Factor::Index(base, index, span) => {
+            let ty = generate_element(text, base, index, *span, stack_info)?;
+            text.push_str("movq %rax, %rdi\n");
+            Some((String::from("(%rdi)"), ty))
+        }
+        Factor::Member(op, base, name, span) => {
+            let ty = generate_member(text, *op, base, name, *span, stack_info)?;
+            text.push_str("movq %rax, %rdi\n");
+            Some((String::from("(%rdi)"), ty))
+        }
+        Factor::Expr(inner, span) => match inner.as_ref() {
+            Expression::Conditional(conditional) => match conditional.as_factor() {
+                Some(factor) => generate_lvalue(text, factor, stack_info),
+                None => not_assignable(*span, stack_info),
+            },
+            Expression::Assign(..) => not_assignable(*span, stack_info),
+        },
+        _ => not_assignable(target.span(), stack_info),
+    }
+}
+
+// Like `generate_lvalue`, but for a target that will be modified, which
+// can't be an array.
+fn generate_assignable(
+    text: &mut String,
+    target: &Factor,
+    stack_info: &mut StackInfo,
+) -> Option<(String, Type)> {
+    let (operand, ty) = generate_lvalue(text, target, stack_info)?;
+    if ty.is_array() {
+        stack_info.diagnostics.push(
+            Diagnostic::error(
+                format!("array type `{}` is not assignable", ty),
+                target.span(),
+            )
+            .with_code(diagnostics::NOT_ASSIGNABLE)
+            .with_label("cannot assign to this expression"),
+        );
+        return None;
+    }
+    Some((operand, ty))
+}
+
+fn not_assignable(span: Span, stack_info: &mut StackInfo) -> Option<(String, Type)> {
+    stack_info.diagnostics.push(
+        Diagnostic::error("expression is not assignable", span)
+            .with_code(diagnostics::NOT_ASSIGNABLE)
+            .with_label("cannot assign to this expression"),
+    );
+    None
+}
+
+// Reports an implicit conversion of `expr` from `from` to `to` that isn't
+// allowed as if by assignment (C11 6.5.16.1).
+fn check_conversion(from: &Type, to: &Type, expr: &Expression, stack_info: &mut StackInfo) {
+    let message = match (from.pointee(), to.pointee()) {
+        // A struct only converts to itself.
+        _ if !from.is_scalar() || !to.is_scalar() => {
+            if from == to {
+                return;
+            }
+            "incompatible types"
+        }
+        (None, None) => return,
+        (None, Some(_)) if constant::is_null_pointer(expr, stack_info) => return,
+        (None, Some(_)) => "incompatible integer to pointer conversion",
+        (Some(_), None) => "incompatible pointer to integer conversion",
+        (Some(a), Some(b)) if a == b || *a == Type::Void || *b == Type::Void => return,
+        (Some(_), Some(_)) => {
+            stack_info.diagnostics.push(
+                Diagnostic::warning("incompatible pointer types", expr.span())
+                    .with_code(diagnostics::INCOMPATIBLE_POINTER_TYPES)
+                    .with_label(format!("expected `{}`, found `{}`", to, from)),
+            );
+            return;
+        }
+    };
+    stack_info.diagnostics.push(
+        Diagnostic::error(message, expr.span())
+            .with_code(diagnostics::INCOMPATIBLE_TYPES)
+            .with_label(format!("expected `{}`, found `{}`", to, from)),
+    );
+}
+
+pub fn generate_expr(text: &mut String, expr: &Expression, stack_info: &mut StackInfo) -> Type {
+    // <expr> ::= <id> <assign-op> <expr> | <conditional-expr>
+    match expr {
+        Expression::Assign(op, target, value, span) => {
+            let value_ty = generate_expr(text, value, stack_info);
+            stack_info.push(text);
+            let lvalue = generate_assignable(text, target, stack_info);
+            stack_info.pop(text, "rax");
+            let Some((operand, ty)) = lvalue else {
+                return Type::Int;
+            };
+
+            match compound_op(*op) {
+                // e1 op= e2: rhs in rcx, read e1 into rax, apply, write back.
+                Some(op) => {
+                    let types = operand_types(op, &ty, &value_ty);
+                    let Some((lhs_ty, rhs_ty)) =
+                        types.filter(|_| ty.is_pointer() || !value_ty.is_pointer())
+                    else {
+                        invalid_operands(op, &ty, &value_ty, *span, stack_info);
+                        return ty;
+                    };
+                    if let Some(rhs) = constant::eval(value, stack_info) {
+                        constant::check_operands(op, &ty, &rhs, value.span(), stack_info);
+                    }
+                    prepare_operand(text, &value_ty, &rhs_ty, &lhs_ty);
+                    text.push_str("movq %rax, %rcx\n");
+                    load(text, &ty, &operand);
+                    prepare_operand(text, &ty, &lhs_ty, &rhs_ty);
+                    let result = apply_binary_op(text, op, &lhs_ty, &rhs_ty);
+                    convert(text, &result, &ty);
+                }
+                None => {
+                    check_conversion(&value_ty, &ty, value, stack_info);
+                    convert(text, &value_ty, &ty);
+                }
+            }
+            store(text, &ty, &operand);
+            ty
+        }
+        Expression::Conditional(conditional_expr) => {
+            generate_conditional_expr(text, conditional_expr, stack_info)
+        }
+    }
+}
+
+pub fn generate_statement(text: &mut String, statement: &Statement, stack_info: &mut StackInfo) {
+    // <function> :: "int" <id> "(" ")" "{" { <block-item> } "}"
+    match statement {
+        Statement::Expr(expr, _) => {
+            generate_expr(text, expr, stack_info);
+        }
+        Statement::Null(_) => {}
+        Statement::Block(blocks, _) => generate_block(text, blocks, stack_info),
+        Statement::Return(expr, span) => {
+            let return_type = stack_info.return_type.clone();
+            match expr {
+                Some(expr) if return_type == Type::Void => {
+                    generate_expr(text, expr, stack_info);
+                    let message = format!(
+                        "void function `{}` should not return a value",
+                        stack_info.function
+                    );
+                    stack_info.diagnostics.push(
+                        Diagnostic::error(message, expr.span())
+                            .with_code(diagnostics::RETURN_VALUE)
+                            .with_label("the function returns `void`"),
+                    );
+                }
+                Some(expr) => {
+                    let ty = generate_expr(text, expr, stack_info);
+                    check_conversion(&ty, &return_type, expr, stack_info);
+                    convert(text, &ty, &return_type);
+                    generate_struct_return(text, &return_type, stack_info);
+                }
+                None if return_type != Type::Void => {
+                    let message = format!(
+                        "non-void function `{}` should return a value",
+                        stack_info.function
+                    );
+                    stack_info.diagnostics.push(
+                        Diagnostic::error(message, *span)
+                            .with_code(diagnostics::RETURN_VALUE)
+                            .with_label(format!("the function returns `{}`", return_type)),
+                    );
+                }
+                None => {}
+            }
+            text.push_str("movq %rbp, %rsp\npop %rbp\nret\n");
+        }
+        Statement::If(expr, if_state, else_state, _) => {
+            let ty = generate_expr(text, expr, stack_info);
+            check_scalar(&ty, expr.span(), stack_info);
+            let c = stack_info.label();
+            compare_zero(text, &ty);
+            text.push_str(format!("je _e{}\n", c).as_str());
+            generate_statement(text, if_state, stack_info);
+
+            match else_state {
+                Some(s) => {
+                    text.push_str(format!("jmp _post_cond{}\n_e{}:\n", c, c).as_str());
+                    generate_statement(text, s, stack_info);
+                    text.push_str(format!("_post_cond{}:\n", c).as_str());
+                }
+                None => {
+                    text.push_str(format!("_e{}:\n", c).as_str());
+                }
+            }
+        }
+        Statement::While(expr, body, _) => {
+            let c = stack_info.label();
+            text.push_str(format!("_loop{}:\n", c).as_str());
+            let ty = generate_expr(text, expr, stack_info);
+            check_scalar(&ty, expr.span(), stack_info);
+            compare_zero(text, &ty);
+            text.push_str(format!("je _break{}\n", c).as_str());
+            generate_loop_body(text, body, c, stack_info);
+            text.push_str(format!("_continue{}:\njmp _loop{}\n_break{}:\n", c, c, c).as_str());
+        }
+        Statement::DoWhile(body, expr, _) => {
+            let c = stack_info.label();
+            text.push_str(format!("_loop{}:\n", c).as_str());
+            generate_loop_body(text, body, c, stack_info);
+            text.push_str(format!("_continue{}:\n", c).as_str());
+            let ty = generate_expr(text, expr, stack_info);
+            check_scalar(&ty, expr.span(), stack_info);
+            compare_zero(text, &ty);
+            text.push_str(format!("jne _loop{}\n_break{}:\n", c, c).as_str());
+        }
+        Statement::For(init, condition, post, body, _) => {
+            // A declaration in the init clause is scoped to the loop.
+            let stack_index = stack_info.enter_scope();
+            match init {
+                Some(ForInit::Declaration(declarations)) => {
+                    for d in declarations {
+                        generate_declaration(text, d, stack_info);
+                    }
+                }
+                Some(ForInit::Expr(e)) => {
+                    generate_expr(text, e, stack_info);
+                }
+                None => {}
+            }
+
+            let c = stack_info.label();
+            text.push_str(format!("_loop{}:\n", c).as_str());
+            if let Some(condition) = condition {
+                let ty = generate_expr(text, condition, stack_info);
+                check_scalar(&ty, condition.span(), stack_info);
+                compare_zero(text, &ty);
+                text.push_str(format!("je _break{}\n", c).as_str());
+            }
+            generate_loop_body(text, body, c, stack_info);
+            text.push_str(format!("_continue{}:\n", c).as_str());
+            if let Some(post) = post {
+                generate_expr(text, post, stack_info);
+            }
+            text.push_str(format!("jmp _loop{}\n_break{}:\n", c, c).as_str());
+
+            stack_info.exit_scope(text, stack_index);
+        }
+        Statement::Break(span) | Statement::Continue(span) => {
+            let (targets, keyword, within) = match statement {
+                Statement::Break(_) => (&stack_info.breaks, "break", "a loop or switch"),
+                _ => (&stack_info.continues, "continue", "a loop"),
+            };
+            match targets.last().cloned() {
+                Some((label, stack_index)) => {
+                    if stack_index != stack_info.stack_index {
+                        let size = stack_index - stack_info.stack_index;
+                        text.push_str(format!("addq ${}, %rsp\n", size).as_str());
+                    }
+                    text.push_str(format!("jmp {}\n", label).as_str());
+                }
+                None => stack_info.diagnostics.push(
+                    Diagnostic::error(
+                        format!("`{}` statement not within {}", keyword, within),
+                        *span,
+                    )
+                    .with_code(diagnostics::JUMP_OUTSIDE_LOOP)
+                    .with_label(format!("cannot `{}` outside of {}", keyword, within)),
+                ),
+            }
+        }
+        Statement::Switch(expr, body, _) => {
+            let ty = generate_expr(text, expr, stack_info).promote();
+            if !ty.is_integer() {
+                stack_info.diagnostics.push(
+                    Diagnostic::error(
+                        format!(
+                            "statement requires expression of integer type (`{}` invalid)",
+                            ty
+                        ),
+                        expr.span(),
+                    )
+                    .with_code(diagnostics::INVALID_OPERANDS)
+                    .with_label("not an integer"),
+                );
+            }
+            let c = stack_info.label();
+
+            let mut cases = Vec::new();
+            collect_cases(body, &mut cases);
+            let labels = (0..cases.len())
+                .map(|i| format!("_case{}_{}", c, i))
+                .collect();
+            generate_dispatch(text, c, &ty, &cases, stack_info);
+
+            stack_info.switches.push(labels);
+            stack_info
+                .breaks
+                .push((format!("_break{}", c), stack_info.stack_index));
+            generate_statement(text, body, stack_info);
+            stack_info.breaks.pop();
+            stack_info.switches.pop();
+
+            text.push_str(format!("_break{}:\n", c).as_str());
+        }
+        Statement::Goto(name, span) => {
+            if stack_info.labels.contains_key(name) {
+                text.push_str(format!("jmp {}\n", asm_label(stack_info, name)).as_str());
+            } else {
+                stack_info.diagnostics.push(
+                    Diagnostic::error(format!("use of undeclared label `{}`", name), *span)
+                        .with_code(diagnostics::UNDEFINED_LABEL)
+                        .with_label("no such label in this function"),
+                );
+            }
+        }
+        Statement::Labeled(name, inner, _) => {
+            // A goto may come from a different block depth, so reset the stack
+            // pointer to this block's.
+            text.push_str(format!("{}:\n", asm_label(stack_info, name)).as_str());
+            text.push_str(format!("leaq {}(%rbp), %rsp\n", stack_info.stack_index).as_str());
+            generate_statement(text, inner, stack_info);
+        }
+        Statement::Case(_, inner, span) | Statement::Default(inner, span) => {
+            // The dispatch jumps here from the switch's block depth, so reset
+            // the stack pointer like a labeled statement does.
+            match stack_info.switches.last_mut().and_then(VecDeque::pop_front) {
+                Some(label) => {
+                    text.push_str(format!("{}:\n", label).as_str());
+                    text.push_str(
+                        format!("leaq {}(%rbp), %rsp\n", stack_info.stack_index).as_str(),
+                    );
+                }
+                None => {
+                    let keyword = match statement {
+                        Statement::Case(..) => "case",
+                        _ => "default",
+                    };
+                    stack_info.diagnostics.push(
+                        Diagnostic::error(
+                            format!("`{}` label not within a switch statement", keyword),
+                            *span,
+                        )
+                        .with_code(diagnostics::CASE_OUTSIDE_SWITCH),
+                    );
+                }
+            }
+            generate_statement(text, inner, stack_info);
+        }
+    }
+}
+
+// Collects the `case` and `default` labels belonging to a switch with this
+// body, in source order. Nested switches own their own labels.
+fn collect_cases<'a>(statement: &'a Statement, cases: &mut Vec<&'a Statement>) {
+    match statement {
+        Statement::Case(_, inner, _) | Statement::Default(inner, _) => {
+            cases.push(statement);
+            collect_cases(inner, cases);
+        }
+        Statement::If(_, if_state, else_state, _) => {
+            collect_cases(if_state, cases);
+            if let Some(s) = else_state {
+                collect_cases(s, cases);
+            }
+        }
+        Statement::Block(blocks, _) => {
+            for block in blocks.iter() {
+                if let BlockItem::Statement(s) = block {
+                    collect_cases(s, cases);
+                }
+            }
+        }
+        Statement::While(_, body, _)
+        | Statement::DoWhile(body, _, _)
+        | Statement::For(_, _, _, body, _)
+        | Statement::Labeled(_, body, _) => collect_cases(body, cases),
+        Statement::Return(..)
+        | Statement::Expr(..)
+        | Statement::Break(_)
+        | Statement::Continue(_)
+        | Statement::Null(_)
+        | Statement::Goto(..)
+        | Statement::Switch(..) => {}
+    }
+}
+
+// Records every label in a function body, reporting duplicates.
+fn collect_labels(statement: &Statement, stack_info: &mut StackInfo) {
+    match statement {
+        Statement::Labeled(name, inner, span) => {
+            let span = label_span(name, *span);
+            match stack_info.labels.get(name) {
+                Some(previous) => {
+                    let diagnostic =
+                        Diagnostic::error(format!("redefinition of label `{}`", name), span)
+                            .with_code(diagnostics::DUPLICATE_LABEL)
+                            .with_label("redefined here")
+                            .with_secondary(*previous, "previous definition is here");
+                    stack_info.diagnostics.push(diagnostic);
+                }
+                None => {
+                    stack_info.labels.insert(name.to_string(), span);
+                }
+            }
+            collect_labels(inner, stack_info);
+        }
+        Statement::If(_, if_state, else_state, _) => {
+            collect_labels(if_state, stack_info);
+            if let Some(s) = else_state {
+                collect_labels(s, stack_info);
+            }
+        }
+        Statement::Block(blocks, _) => {
+            for block in blocks.iter() {
+                if let BlockItem::Statement(s) = block {
+                    collect_labels(s, stack_info);
+                }
+            }
+        }
+        Statement::While(_, body, _)
+        | Statement::DoWhile(body, _, _)
+        | Statement::For(_, _, _, body, _)
+        | Statement::Switch(_, body, _)
+        | Statement::Case(_, body, _)
+        | Statement::Default(body, _) => collect_labels(body, stack_info),
+        Statement::Return(..)
+        | Statement::Expr(..)
+        | Statement::Break(_)
+        | Statement::Continue(_)
+        | Statement::Null(_)
+        | Statement::Goto(..) => {}
+    }
+}
+
+// Gives each call in a function body that returns a struct its own slot at
+// the top of the frame, and reserves them. Values returned into a temporary
+// pushed mid-expression would move the operands already on the stack.
+fn reserve_return_slots(text: &mut String, blocks: &[BlockItem], stack_info: &mut StackInfo) {
+    let mut calls = Vec::new();
+    collect_calls_in_block(blocks, &mut calls);
+    stack_info.return_slots.clear();
+    let mut size = 0;
+    for call in calls {
+        let Factor::Call(name, _, _) = call else {
+            continue;
+        };
+        let Some(info) = stack_info.functions.get(name) else {
+            continue;
+        };
+        if !info.return_type.is_scalar() && info.return_type != Type::Void {
+            size += (info.return_type.size() + 7) / 8 * 8;
+            stack_info.return_slots.insert(call, -size);
+        }
+    }
+    stack_info.allocate(text, size);
+}
+
+// Collects the calls in a block, including those in initializers.
+fn collect_calls_in_block<'a>(blocks: &'a [BlockItem], calls: &mut Vec<&'a Factor>) {
+    for block in blocks.iter() {
+        match block {
+            BlockItem::Statement(s) => collect_calls_in_statement(s, calls),
+            BlockItem::Declaration(declarations) => {
+                collect_calls_in_declarations(declarations, calls)
+            }
+            BlockItem::Tag(..) => {}
+        }
+    }
+}
+
+fn collect_calls_in_declarations<'a>(declarations: &'a [Declaration], calls: &mut Vec<&'a Factor>) {
+    for init in declarations.iter().filter_map(|d| d.init.as_ref()) {
+        collect_calls_in_initializer(init, calls);
+    }
+}
+
+fn collect_calls_in_statement<'a>(statement: &'a Statement, calls: &mut Vec<&'a Factor>) {
+    match statement {
+        Statement::Return(Some(expr), _) | Statement::Expr(expr, _) => collect_calls(expr, calls),
+        Statement::If(expr, if_state, else_state, _) => {
+            collect_calls(expr, calls);
+            collect_calls_in_statement(if_state, calls);
+            if let Some(s) = else_state {
+                collect_calls_in_statement(s, calls);
+            }
+        }
+        Statement::Block(blocks, _) => collect_calls_in_block(blocks, calls),
+        Statement::While(expr, body, _)
+        | Statement::DoWhile(body, expr, _)
+        | Statement::Switch(expr, body, _)
+        | Statement::Case(expr, body, _) => {
+            collect_calls(expr, calls);
+            collect_calls_in_statement(body, calls);
+        }
+        Statement::For(init, condition, post, body, _) => {
+            match init {
+                Some(ForInit::Declaration(declarations)) => {
+                    collect_calls_in_declarations(declarations, calls)
+                }
+                Some(ForInit::Expr(e)) => collect_calls(e, calls),
+                None => {}
+            }
+            for expr in condition.iter().chain(post.iter()) {
+                collect_calls(expr, calls);
+            }
+            collect_calls_in_statement(body, calls);
+        }
+        Statement::Default(body, _) | Statement::Labeled(_, body, _) => {
+            collect_calls_in_statement(body, calls)
+        }
+        Statement::Return(None, _)
+        | Statement::Break(_)
+        | Statement::Continue(_)
+        | Statement::Goto(..)
+        | Statement::Null(_) => {}
+    }
+}
+
+fn collect_calls_in_initializer<'a>(init: &'a Initializer, calls: &mut Vec<&'a Factor>) {
+    match init {
+        Initializer::Expr(expr) => collect_calls(expr, calls),
+        Initializer::List(items, _) => {
+            for (_, item) in items.iter() {
+                collect_calls_in_initializer(item, calls);
+            }
+        }
+    }
+}
+
+fn collect_calls<'a>(expr: &'a Expression, calls: &mut Vec<&'a Factor>) {
+    match expr {
+        Expression::Assign(_, target, value, _) => {
+            collect_calls_in_factor(target, calls);
+            collect_calls(value, calls);
+        }
+        Expression::Conditional(cond) => collect_calls_in_conditional(cond, calls),
+    }
+}
+
+fn collect_calls_in_conditional<'a>(cond: &'a ConditionalExpr, calls: &mut Vec<&'a Factor>) {
+    for factor in cond.factors() {
+        collect_calls_in_factor(factor, calls);
+    }
+    if let Some((a, b)) = &cond.additional {
+        collect_calls(a, calls);
+        collect_calls_in_conditional(b, calls);
+    }
+}
+
+fn collect_calls_in_factor<'a>(factor: &'a Factor, calls: &mut Vec<&'a Factor>) {
+    match factor {
+        Factor::Call(_, args, _) => {
+            calls.push(factor);
+            for arg in args.iter() {
+                collect_calls(arg, calls);
+            }
+        }
+        Factor::Expr(expr, _) => collect_calls(expr, calls),
+        Factor::Index(base, index, _) => {
+            collect_calls_in_factor(base, calls);
+            collect_calls(index, calls);
+        }
+        Factor::UnaryOp(_, inner, _)
+        | Factor::Cast(_, inner, _)
+        | Factor::Postfix(_, inner, _)
+        | Factor::Member(_, inner, _, _)
+        | Factor::SizeOf(inner, _) => collect_calls_in_factor(inner, calls),
+        Factor::SizeOfType(..)
+        | Factor::Number(..)
+        | Factor::String(..)
+        | Factor::Identifier(..) => {}
+    }
+}
+
+// The span of just the label name at the start of a labeled statement.
+fn label_span(name: &str, statement: Span) -> Span {
+    Span {
+        end: statement.start + name.len(),
+        ..statement
+    }
+}
+
+// The assembly label for C label `name`. `.` can't appear in identifiers, so
+// labels of different functions can't collide.
+fn asm_label(stack_info: &StackInfo, name: &str) -> String {
+    format!(".L{}.{}", stack_info.function, name)
+}
+
+// Jumps from the switch value of promoted type `ty` in %rax to the matching
+// case of switch number `c`, or to its default (`_break<c>` if there is
+// none). Dense cases of 32-bit switches use a jump table, everything else a
+// chain of comparisons.
+fn generate_dispatch(
+    text: &mut String,
+    c: u32,
+    ty: &Type,
+    cases: &[&Statement],
+    stack_info: &mut StackInfo,
+) {
+    let mut values: Vec<(i64, String)> = Vec::new();
+    let mut seen: HashMap<i64, Span> = HashMap::new();
+    let mut default: Option<(String, Span)> = None;
+
+    for (i, case) in cases.iter().enumerate() {
+        let label = format!("_case{}_{}", c, i);
+        match case {
+            Statement::Case(expr, _, span) => match constant::eval(expr, stack_info) {
+                Some((value, _)) => {
+                    // Case values are converted to the type of the switch.
+                    let value = constant::cast(value, ty);
+                    if let Some(previous) = seen.get(&value) {
+                        stack_info.diagnostics.push(
+                            Diagnostic::error(format!("duplicate case value `{}`", value), *span)
+                                .with_code(diagnostics::DUPLICATE_CASE)
+                                .with_label("duplicate case")
+                                .with_secondary(*previous, "previous case is here"),
+                        );
+                    } else {
+                        seen.insert(value, *span);
+                        values.push((value, label));
+                    }
+                }
+                None => stack_info.diagnostics.push(
+                    Diagnostic::error(
+                        "case label does not reduce to an integer constant",
+                        expr.span(),
+                    )
+                    .with_code(diagnostics::NOT_CONSTANT)
+                    .with_label("not a constant expression"),
+                ),
+            },
+            _ => match &default {
+                Some((_, previous)) => stack_info.diagnostics.push(
+                    Diagnostic::error("multiple default labels in one switch", case.span())
+                        .with_code(diagnostics::DUPLICATE_CASE)
+                        .with_label("duplicate default")
+                        .with_secondary(*previous, "previous default is here"),
+                ),
+                None => default = Some((label, case.span())),
+            },
+        }
+    }
+
+    let fallback = match default {
+        Some((label, _)) => label,
+        None => format!("_break{}", c),
+    };
+    values.sort_by_key(|(value, _)| *value);
+
+    // Use a table when at least a third of the entries would be real cases.
+    let (min, max) = match (values.first(), values.last()) {
+        (Some(first), Some(last)) => (first.0, last.0),
+        _ => (0, 0),
+    };
+    let range = max.wrapping_sub(min).wrapping_add(1);
+    if ty.size() == 8 || values.len() < 4 || range > 3 * values.len() as i64 {
+        for (value, label) in values.iter() {
+            if ty.size() == 8 && i32::try_from(*value).is_err() {
+                text.push_str(format!("movabsq ${}, %rcx\ncmpq %rcx, %rax\n", value).as_str());
+            } else {
+                let (s, a) = (suffix(ty), rax(ty));
+                text.push_str(format!("cmp{} ${}, {}\n", s, *value as i32, a).as_str());
+            }
+            text.push_str(format!("je {}\n", label).as_str());
+        }
+        text.push_str(format!("jmp {}\n", fallback).as_str());
+        return;
+    }
+
+    // Entries are offsets from the table so it works in position-independent
+    // executables.
+    text.push_str(
+        format!(
+            "subl ${}, %eax\ncmpl ${}, %eax\nja {}\n",
+            min as i32,
+            range - 1,
+            fallback
+        )
+        .as_str(),
+    );
+    text.push_str(format!("leaq _table{}(%rip), %rcx\n", c).as_str());
+    text.push_str("movslq (%rcx,%rax,4), %rax\naddq %rcx, %rax\njmp *%rax\n");
+
+    let tables = &mut stack_info.jump_tables;
+    tables.push_str(format!("_table{}:\n", c).as_str());
+    let mut values = values.iter().peekable();
+    for value in min..=max {
+        let label = match values.next_if(|(v, _)| *v == value) {
+            Some((_, label)) => label,
+            None => &fallback,
+        };
+        tables.push_str(format!(".long {}-_table{}\n", label, c).as_str());
+    }
+}
+
+// Generates the body of loop number `c`, with `break` jumping to `_break<c>`
+// and `continue` to `_continue<c>`.
+fn generate_loop_body(text: &mut String, body: &Statement, c: u32, stack_info: &mut StackInfo) {
+    let stack_index = stack_info.stack_index;
+    stack_info
+        .breaks
+        .push((format!("_break{}", c), stack_index));
+    stack_info
+        .continues
+        .push((format!("_continue{}", c), stack_index));
+    generate_statement(text, body, stack_info);
+    stack_info.breaks.pop();
+    stack_info.continues.pop();
+}
+
+// Generates the items of a `{ ... }` block in a new scope. Variables declared
+// in the block are popped off the stack when it ends.
+pub fn generate_block(text: &mut String, blocks: &[BlockItem], stack_info: &mut StackInfo) {
+    let stack_index = stack_info.enter_scope();
+    generate_items(text, blocks, stack_info);
+    stack_info.exit_scope(text, stack_index);
+}
+
+fn generate_items(text: &mut String, blocks: &[BlockItem], stack_info: &mut StackInfo) {
+    for block in blocks.iter() {
+        match block {
+            BlockItem::Statement(s) => generate_statement(text, s, stack_info),
+            BlockItem::Declaration(declarations) => {
+                for d in declarations {
+                    generate_declaration(text, d, stack_info);
+                }
+            }
+            BlockItem::Tag(ty, _) => generate_tag(ty, stack_info),
+        }
+    }
+}
+
+pub fn generate_declaration(
+    text: &mut String,
+    declaration: &Declaration,
+    stack_info: &mut StackInfo,
+) {
+    // <declaration>> ::= [ "static" | "extern" ] <type> <id> { "[" <exp> "]" } [ = <initializer> ] ";"
+    let name = declaration.identifier.as_str();
+
+    match declaration.storage {
+        // Lives in .data/.bss under a name no other variable can have.
+        Some(TokenType::Static) => {
+            let (ty, scalars) = resolve_declaration(declaration, stack_info);
+            let init = static_values(&scalars, stack_info);
+            if !stack_info.redeclared(name, declaration.span) {
+                let label = format!("{}.{}", name, stack_info.label());
+                let operand = format!("{}(%rip)", label);
+                stack_info.data.push(StaticData {
+                    label,
+                    ty: ty.clone(),
+                    init,
+                    global: false,
+                });
+                stack_info.declare(name, operand, ty, declaration.span);
+            }
+            return;
+        }
+        // Refers to the file-scope variable of the same name.
+        Some(TokenType::Extern) => {
+            if let Some(init) = &declaration.init {
+                stack_info.diagnostics.push(
+                    Diagnostic::error(
+                        format!("`extern` variable `{}` cannot have an initializer", name),
+                        init.span(),
+                    )
+                    .with_code(diagnostics::INVALID_INITIALIZER)
+                    .with_label("initializer not allowed here"),
+                );
+            }
+            let redeclared = stack_info.redeclared(name, declaration.span);
+            let ty = declare_global(declaration, stack_info);
+            if !redeclared {
+                let operand = format!("{}(%rip)", name);
+                stack_info.declare(name, operand, ty, declaration.span);
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    let redeclared = stack_info.redeclared(name, declaration.span);
+    let (ty, scalars) = resolve_declaration(declaration, stack_info);
+
+    if ty.is_scalar() {
+        match scalars.first() {
+            Some((_, _, scalar)) => generate_scalar(text, scalar, &ty, stack_info),
+            // set to 0
+            None => text.push_str("movl $0, %eax\n"),
+        }
+        if redeclared {
+            return;
+        }
+        // Every scalar local gets an 8-byte slot, whatever its size.
+        stack_info.push(text);
+        let operand = format!("{}(%rbp)", stack_info.stack_index);
+        stack_info.declare(name, operand, ty, declaration.span);
+        return;
+    }
+
+    // Arrays and structs get as many 8-byte slots as they need. When
+    // initialized, the whole object is zeroed before the given scalars are
+    // stored.
+    let size = ty.size();
+    let base = stack_info.allocate(text, size);
+    if declaration.init.is_some() {
+        text.push_str(
+            format!(
+                "leaq {}(%rbp), %rdi\nmovl $0, %eax\nmovq ${}, %rcx\nrep stosb\n",
+                base, size
+            )
+            .as_str(),
+        );
+    }
+    for (offset, element, scalar) in scalars.iter() {
+        generate_scalar(text, scalar, element, stack_info);
+        store(text, element, &format!("{}(%rbp)", base + offset));
+    }
+    if !redeclared {
+        stack_info.declare(name, format!("{}(%rbp)", base), ty, declaration.span);
+    }
+}
+
+// Evaluates an initializer for a scalar of type `ty` into %rax.
+fn generate_scalar(text: &mut String, scalar: &Scalar, ty: &Type, stack_info: &mut StackInfo) {
+    match scalar {
+        Scalar::Expr(expr) => {
+            let from = generate_expr(text, expr, stack_info);
+            check_conversion(&from, ty, expr, stack_info);
+            convert(text, &from, ty);
+        }
+        Scalar::Constant(value) => text.push_str(format!("movl ${}, %eax\n", value).as_str()),
+    }
+}
+
+// The type of a declared variable, with its array dimensions, and the
+// scalars its initializer gives a value to, as (offset, type, value).
+fn resolve_declaration<'a>(
+    declaration: &'a Declaration,
+    stack_info: &mut StackInfo,
+) -> (Type, Vec<(i32, Type, Scalar<'a>)>) {
+    let ty = array_type(
+        &declaration.ty,
+        &declaration.dims,
+        declaration.span,
+        stack_info,
+    );
+    let mut scalars = Vec::new();
+    let ty = match &declaration.init {
+        Some(init) => initialize(&ty, init, 0, &mut scalars, stack_info),
+        None => ty,
+    };
+    (complete(ty, declaration, stack_info), scalars)
+}
+
+// Reports an array declared without a length or an initializer to take it
+// from, treating it as having one element, and a variable of a struct type
+// that isn't defined. `extern` declarations can leave these to the
+// definition.
+fn complete(ty: Type, declaration: &Declaration, stack_info: &mut StackInfo) -> Type {
+    if declaration.storage == Some(TokenType::Extern) {
+        return ty;
+    }
+    match ty {
+        Type::Array(element, 0) => {
+            stack_info.diagnostics.push(
+                Diagnostic::error(
+                    format!("array `{}` has no size", declaration.identifier),
+                    declaration.span,
+                )
+                .with_code(diagnostics::INVALID_ARRAY_SIZE)
+                .with_label("needs an explicit size or an initializer"),
+            );
+            Type::Array(element, 1)
+        }
+        _ if !ty.is_complete() => {
+            stack_info.diagnostics.push(
+                Diagnostic::error(
+                    format!(
+                        "variable `{}` has incomplete type `{}`",
+                        declaration.identifier, ty
+                    ),
+                    declaration.span,
+                )
+                .with_code(diagnostics::INCOMPLETE_TYPE)
+                .with_label("declared here"),
+            );
+            ty
+        }
+        _ => ty,
+    }
+}
+
+// The type of a declarator whose base type is `ty` and whose array
+// dimensions, outermost first, are `dims`. An omitted first length is left as
+// 0, to be taken from the initializer.
+fn array_type(
+    ty: &Type,
+    dims: &[Option<Expression>],
+    span: Span,
+    stack_info: &mut StackInfo,
+) -> Type {
+    let mut ty = resolve(ty, stack_info);
+    for (i, dim) in dims.iter().enumerate().rev() {
+        let length = match dim {
+            Some(expr) => match constant::eval(expr, stack_info) {
+                Some((length, ty)) if length > 0 || (ty.is_unsigned() && length != 0) => {
+                    length as usize
+                }
+                Some(_) => {
+                    stack_info.diagnostics.push(
+                        Diagnostic::error("array size must be positive", expr.span())
+                            .with_code(diagnostics::INVALID_ARRAY_SIZE)
+                            .with_label("size declared here"),
+                    );
+                    1
+                }
+                None => {
+                    stack_info.diagnostics.push(
+                        Diagnostic::error(
+                            "array size is not an integer constant expression",
+                            expr.span(),
+                        )
+                        .with_code(diagnostics::NOT_CONSTANT)
+                        .with_label("not a constant expression"),
+                    );
+                    1
+                }
+            },
+            None if i == 0 => 0,
+            None => {
+                stack_info.diagnostics.push(
+                    Diagnostic::error("array has incomplete element type", span)
+                        .with_code(diagnostics::INVALID_ARRAY_SIZE)
+                        .with_label("only the first array size can be omitted"),
+                );
+                1
+            }
+        };
+        ty = Type::Array(Box::new(ty), length);
     }
+    ty
 }
 
-pub fn generate_term(text: &mut String, term: &Term, stack_info: &mut StackInfo) {
-    // <term> ::= <factor> { ("*" | "/") <factor> }
-    generate_factor(text, &term.factor, stack_info);
-
-    for (op, factor) in term.additional.iter() {
-        text.push_str("push %rax\n");
-        generate_factor(text, factor, stack_info);
-
-        match op {
-            TokenType::Multiplication => text.push_str("pop %rcx\nimul %ecx, %eax\n"),
-            TokenType::Division => {
-                // e1 in eax, e2 in ecx, then sign extend (cdq) eax into [edx:eax]
-                // stores quotient in eax, remainder in edx
-                text.push_str("movl %eax, %ecx\npop %rax\ncdq\nidivl %ecx\n");
-            }
-            _ => {
-                dbg!(op);
-                panic!();
+// The type of a parameter: arrays are adjusted to pointers to their first
+// element (C11 6.7.6.3p7).
+fn param_type(param: &Param, stack_info: &mut StackInfo) -> Type {
+    match param.dims.split_first() {
+        Some((_, inner)) => Type::Pointer(Box::new(array_type(
+            &param.ty, inner, param.span, stack_info,
+        ))),
+        None => {
+            let ty = resolve(&param.ty, stack_info);
+            if ty == Type::Void {
+                let message = match &param.name {
+                    Some(name) => format!("parameter `{}` has type `void`", name),
+                    None => String::from("parameter has type `void`"),
+                };
+                stack_info.diagnostics.push(
+                    Diagnostic::error(message, param.span)
+                        .with_code(diagnostics::INCOMPLETE_TYPE)
+                        .with_label("declared here")
+                        .with_note("`(void)` on its own declares a function without parameters"),
+                );
             }
+            ty
         }
     }
 }
 
-pub fn generate_add_expr(text: &mut String, add_expr: &AdditiveExpr, stack_info: &mut StackInfo) {
-    // <add-expr> ::= <term> { ("+" | "-") <term> }
-    generate_term(text, &add_expr.term, stack_info);
-
-    for (op, expr) in add_expr.additional.iter() {
-        text.push_str("push %rax\n");
-        generate_term(text, expr, stack_info);
-
-        match op {
-            TokenType::Addition => text.push_str("pop %rcx\naddl %ecx, %eax\n"),
-            TokenType::Minus => text.push_str("movl %eax, %ecx\npop %rax\nsubl %ecx, %eax\n"),
-            _ => {
-                dbg!(op);
-                panic!();
-            }
+// `ty` with its struct, union and enum specifiers looked up, or defined, in
+// the current scope.
+fn resolve(ty: &Type, stack_info: &mut StackInfo) -> Type {
+    match ty {
+        Type::Pointer(inner) => Type::Pointer(Box::new(resolve(inner, stack_info))),
+        Type::Array(element, length) => {
+            Type::Array(Box::new(resolve(element, stack_info)), *length)
         }
+        Type::Specifier(specifier) => resolve_tag(specifier, false, stack_info),
+        _ => ty.clone(),
     }
 }
 
-pub fn generate_shift_expr(text: &mut String, shift_expr: &ShiftExpr, stack_info: &mut StackInfo) {
-    // <shift-expr> ::= <add-expr> { ("<<" | ">>") <add-expr> }
-    generate_add_expr(text, &shift_expr.add_expr, stack_info);
-
-    for (op, expr) in shift_expr.additional.iter() {
-        text.push_str("push %rax\n");
-        generate_add_expr(text, expr, stack_info);
-        text.push_str("movl %eax, %ecx\npop %rax\n");
+// Handles a declaration of only a tag. `struct s;` declares `s` in the
+// current scope even if an outer one has it, so it can be defined anew
+// (C11 6.7.2.3p7).
+fn generate_tag(ty: &Type, stack_info: &mut StackInfo) {
+    match ty {
+        Type::Specifier(specifier) => {
+            resolve_tag(specifier, true, stack_info);
+        }
+        _ => {
+            resolve(ty, stack_info);
+        }
+    }
+}
 
-        match op {
-            TokenType::LBitShift => text.push_str("sal %ecx, %eax\n"),
-            TokenType::RBitShift => text.push_str("sar %ecx, %eax\n"),
-            _ => {
-                dbg!(op);
-                panic!();
+// The type a specifier names. A definition, or a forward declaration,
+// declares its tag in the current scope; any other use refers to the
+// innermost declaration of the tag, or else declares it there, incomplete.
+fn resolve_tag(specifier: &Rc<TagSpecifier>, forward: bool, stack_info: &mut StackInfo) -> Type {
+    // A specifier may be resolved more than once, as when a declaration is
+    // both checked and generated, but defines its type only the first time.
+    let key = Rc::as_ptr(specifier);
+    if let Some(ty) = stack_info.specifiers.get(&key) {
+        return ty.clone();
+    }
+    let found = match &specifier.tag {
+        None => None,
+        Some(name) if specifier.body.is_some() || forward => {
+            stack_info.tags.last().unwrap().get(name).cloned()
+        }
+        Some(name) => stack_info
+            .tags
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned(),
+    };
+    let tag = match (found, &specifier.tag) {
+        (Some((tag, previous)), _) => match check_tag(&tag, previous, specifier) {
+            Some(diagnostic) => {
+                stack_info.diagnostics.push(diagnostic);
+                new_tag(specifier)
             }
+            None => tag,
+        },
+        (None, Some(name)) if specifier.kind == TokenType::Enum && specifier.body.is_none() => {
+            stack_info.diagnostics.push(
+                Diagnostic::error(
+                    format!("forward reference to `{}`", specifier),
+                    specifier.span,
+                )
+                .with_code(diagnostics::INCOMPLETE_TYPE)
+                .with_label(format!("`enum {}` is not defined here", name)),
+            );
+            Tag::Enum
+        }
+        (None, Some(name)) => {
+            let tag = new_tag(specifier);
+            stack_info
+                .tags
+                .last_mut()
+                .unwrap()
+                .insert(name.clone(), (tag.clone(), specifier.span));
+            tag
+        }
+        (None, None) => new_tag(specifier),
+    };
+    let ty = match &tag {
+        Tag::Aggregate(aggregate) => Type::Struct(aggregate.clone()),
+        Tag::Enum => Type::Int,
+    };
+    // Declared before its members are, so they can point to it.
+    stack_info.specifiers.insert(key, ty.clone());
+    match (&tag, &specifier.body) {
+        (Tag::Aggregate(aggregate), Some(TagBody::Fields(fields))) => {
+            define_fields(aggregate, fields, stack_info)
+        }
+        (Tag::Enum, Some(TagBody::Enumerators(enumerators))) => {
+            define_enumerators(enumerators, stack_info)
         }
+        _ => {}
     }
+    ty
 }
 
-pub fn generate_rel_expr(text: &mut String, rel_expr: &RelationalExpr, stack_info: &mut StackInfo) {
-    // <rel-expr> ::= <shift-expr> { ("<" | ">" | "<=" | ">=") <shift-expr> }
-    generate_shift_expr(text, &rel_expr.shift_expr, stack_info);
+// A new, incomplete, type of the kind `specifier` names.
+fn new_tag(specifier: &TagSpecifier) -> Tag {
+    match specifier.kind {
+        TokenType::Enum => Tag::Enum,
+        kind => Tag::Aggregate(Rc::new(Aggregate::new(
+            kind == TokenType::Union,
+            specifier.tag.clone(),
+        ))),
+    }
+}
 
-    for (op, expr) in rel_expr.additional.iter() {
-        text.push_str("push %rax\n");
-        generate_shift_expr(text, expr, stack_info);
-        text.push_str("pop %rcx\ncmpl %eax, %ecx\nmovl $0, %eax\n");
+// Reports a use of a tag that contradicts its earlier declaration at
+// `previous`: as another kind of type, or defining it a second time.
+fn check_tag(tag: &Tag, previous: Span, specifier: &TagSpecifier) -> Option<Diagnostic> {
+    let (kind, complete) = match tag {
+        Tag::Aggregate(aggregate) if aggregate.union => (TokenType::Union, aggregate.is_complete()),
+        Tag::Aggregate(aggregate) => (TokenType::Struct, aggregate.is_complete()),
+        Tag::Enum => (TokenType::Enum, true),
+    };
+    if kind != specifier.kind {
+        Some(
+            Diagnostic::error(
+                format!(
+                    "use of `{}` with tag type that does not match previous declaration",
+                    specifier
+                ),
+                specifier.span,
+            )
+            .with_code(diagnostics::CONFLICTING_TYPES)
+            .with_label("wrong kind of tag")
+            .with_secondary(previous, "previous declaration is here"),
+        )
+    } else if specifier.body.is_some() && complete {
+        Some(
+            Diagnostic::error(format!("redefinition of `{}`", specifier), specifier.span)
+                .with_code(diagnostics::REDECLARATION)
+                .with_label("redefined here")
+                .with_secondary(previous, "previous definition is here"),
+        )
+    } else {
+        None
+    }
+}
 
-        match op {
-            TokenType::LessThan => text.push_str("setl %al\n"),
-            TokenType::LessThanEqual => text.push_str("setle %al\n"),
-            TokenType::GreaterThan => text.push_str("setg %al\n"),
-            TokenType::GreaterThanEqual => text.push_str("setge %al\n"),
-            _ => {
-                dbg!(op);
-                panic!();
-            }
+// Declares the enumerators of an enum definition as constants in the current
+// scope, each visible from its own declaration on.
+fn define_enumerators(enumerators: &[Enumerator], stack_info: &mut StackInfo) {
+    let mut next: i128 = 0;
+    for enumerator in enumerators {
+        let value = match &enumerator.value {
+            Some(expr) => match constant::eval(expr, stack_info) {
+                Some((value, ty)) if ty.is_unsigned() && value < 0 => value as u64 as i128,
+                Some((value, _)) => value as i128,
+                None => {
+                    stack_info.diagnostics.push(
+                        Diagnostic::error(
+                            "enumerator value is not an integer constant expression",
+                            expr.span(),
+                        )
+                        .with_code(diagnostics::NOT_CONSTANT)
+                        .with_label("not a constant expression"),
+                    );
+                    next
+                }
+            },
+            None => next,
+        };
+        // Enumerators have type `int` (C11 6.7.2.2p2).
+        if i32::try_from(value).is_err() {
+            stack_info.diagnostics.push(
+                Diagnostic::error(
+                    format!("enumerator value {} is not representable in `int`", value),
+                    enumerator.span,
+                )
+                .with_code(diagnostics::ENUMERATOR_RANGE)
+                .with_label(format!("`{}` declared here", enumerator.name)),
+            );
+        }
+        let value = value as i32 as i64;
+        next = value as i128 + 1;
+        if !stack_info.redeclared(&enumerator.name, enumerator.span) {
+            let scope = stack_info.scopes.last_mut().unwrap();
+            scope.insert(
+                enumerator.name.clone(),
+                (Symbol::Constant(value), enumerator.span),
+            );
         }
     }
 }
 
-pub fn generate_eq_expr(text: &mut String, eq_expr: &EqualityExpr, stack_info: &mut StackInfo) {
-    // <eq-expr> ::= <rel-expr> { ("!=" | "==") <rel-expr> }
-    generate_rel_expr(text, &eq_expr.rel_expr, stack_info);
-
-    for (op, expr) in eq_expr.additional.iter() {
-        text.push_str("push %rax\n");
-        generate_rel_expr(text, expr, stack_info);
-        text.push_str("pop %rcx\ncmpl %eax, %ecx\nmovl $0, %eax\n");
-
-        match op {
-            TokenType::Equal => text.push_str("sete %al\n"),
-            TokenType::NotEqual => text.push_str("setne %al\n"),
-            _ => {
-                dbg!(op);
-                panic!();
+// Lays out the members of a struct or union definition.
+fn define_fields(aggregate: &Aggregate, fields: &[Field], stack_info: &mut StackInfo) {
+    let mut members = Vec::new();
+    let mut names: HashMap<String, Span> = HashMap::new();
+    for (i, field) in fields.iter().enumerate() {
+        let ty = array_type(&field.ty, &field.dims, field.span, stack_info);
+        // A struct may end with an array of unknown length (C11 6.7.2.1p18).
+        let flexible = matches!(&ty, Type::Array(element, 0) if element.is_complete())
+            && i == fields.len() - 1
+            && !aggregate.union;
+        if !ty.is_complete() && !flexible {
+            stack_info.diagnostics.push(
+                Diagnostic::error(
+                    format!(
+                        "field `{}` has incomplete type `{}`",
+                        field.name.as_deref().unwrap_or("<anonymous>"),
+                        ty
+                    ),
+                    field.span,
+                )
+                .with_code(diagnostics::INCOMPLETE_TYPE)
+                .with_label("declared here"),
+            );
+            continue;
+        }
+        for name in member_names(&field.name, &ty) {
+            if let Some(previous) = names.insert(name.clone(), field.span) {
+                stack_info.diagnostics.push(
+                    Diagnostic::error(format!("duplicate member `{}`", name), field.span)
+                        .with_code(diagnostics::REDECLARATION)
+                        .with_label("redeclared here")
+                        .with_secondary(previous, "previous declaration is here"),
+                );
             }
         }
+        members.push((field.name.clone(), ty));
     }
+    aggregate.define(members);
 }
 
-pub fn generate_bit_and_expr(
-    text: &mut String,
-    bit_and_expr: &BitAndExpr,
-    stack_info: &mut StackInfo,
-) {
-    // <bit-and-expr> ::= <eq-expr> { "&" <eq-expr> }
-    generate_eq_expr(text, &bit_and_expr.eq_expr, stack_info);
-
-    for expr in bit_and_expr.additional.iter() {
-        text.push_str("push %rax\n");
-        generate_eq_expr(text, expr, stack_info);
-        text.push_str("pop %rcx\nand %ecx, %eax\n");
+// The names a member declares: its own, or those of the members of an
+// anonymous struct or union.
+fn member_names(name: &Option<String>, ty: &Type) -> Vec<String> {
+    match (name, ty) {
+        (Some(name), _) => vec![name.clone()],
+        (None, Type::Struct(aggregate)) => aggregate
+            .members()
+            .iter()
+            .flat_map(|member| member_names(&member.name, &member.ty))
+            .collect(),
+        (None, _) => Vec::new(),
     }
 }
 
-pub fn generate_bit_xor_expr(
-    text: &mut String,
-    bit_xor_expr: &BitXOrExpr,
+// Collects the scalars `init` gives a value to in an object of type `ty` at
+// `offset`; a struct may also be initialized as a whole by an expression.
+// Returns `ty`, with the length of an array declared without one filled in.
+fn initialize<'a>(
+    ty: &Type,
+    init: &'a Initializer,
+    offset: i32,
+    scalars: &mut Vec<(i32, Type, Scalar<'a>)>,
     stack_info: &mut StackInfo,
-) {
-    // <bit-xor-expr> ::= <bit-and-expr> { "^" <bit-and-expr> }
-    generate_bit_and_expr(text, &bit_xor_expr.bit_and_expr, stack_info);
-
-    for expr in bit_xor_expr.additional.iter() {
-        text.push_str("push %rax\n");
-        generate_bit_and_expr(text, expr, stack_info);
-        text.push_str("pop %rcx\nxor %ecx, %eax\n");
+) -> Type {
+    match (ty, init) {
+        (Type::Array(element, length), Initializer::Expr(expr)) => {
+            let Some(bytes) = string_initializer(ty, init) else {
+                stack_info.diagnostics.push(
+                    Diagnostic::error("array initializer must be an initializer list", expr.span())
+                        .with_code(diagnostics::INVALID_INITIALIZER)
+                        .with_label(format!("initializing `{}`", ty)),
+                );
+                return ty.clone();
+            };
+            // The terminating null is left out if there is no room for it.
+            let length = match *length {
+                0 => bytes.len() + 1,
+                length => length,
+            };
+            if bytes.len() > length {
+                stack_info.diagnostics.push(
+                    Diagnostic::error("initializer-string for char array is too long", expr.span())
+                        .with_code(diagnostics::INVALID_INITIALIZER)
+                        .with_label(format!("initializing `{}`", ty)),
+                );
+            }
+            for (i, byte) in bytes.iter().take(length).enumerate() {
+                let value = constant::cast(*byte as i64, element);
+                scalars.push((
+                    offset + i as i32,
+                    (**element).clone(),
+                    Scalar::Constant(value),
+                ));
+            }
+            Type::Array(element.clone(), length)
+        }
+        (Type::Array(..) | Type::Struct(_), Initializer::List(items, _)) => {
+            let mut items = items.iter().peekable();
+            let ty = initialize_list(ty, &mut items, offset, true, scalars, stack_info);
+            if let Some((_, extra)) = items.next() {
+                let kind = match &ty {
+                    Type::Struct(aggregate) if aggregate.union => "union",
+                    Type::Struct(_) => "struct",
+                    _ => "array",
+                };
+                stack_info.diagnostics.push(
+                    Diagnostic::error(
+                        format!("excess elements in {} initializer", kind),
+                        extra.span(),
+                    )
+                    .with_code(diagnostics::INVALID_INITIALIZER)
+                    .with_label(format!("initializing `{}`", ty)),
+                );
+            }
+            ty
+        }
+        (_, Initializer::Expr(expr)) => {
+            scalars.push((offset, ty.clone(), Scalar::Expr(expr)));
+            ty.clone()
+        }
+        // A scalar may be initialized from a list with one element.
+        (_, Initializer::List(items, span)) => {
+            match items.split_first() {
+                Some(((designators, first), rest)) => {
+                    if let Some((_, extra)) = rest.first() {
+                        stack_info.diagnostics.push(
+                            Diagnostic::error(
+                                "excess elements in scalar initializer",
+                                extra.span(),
+                            )
+                            .with_code(diagnostics::INVALID_INITIALIZER)
+                            .with_label(format!("initializing `{}`", ty)),
+                        );
+                    }
+                    if let Some(designator) = designators.first() {
+                        stack_info.diagnostics.push(
+                            Diagnostic::error(
+                                format!("designator in initializer for scalar type `{}`", ty),
+                                designator.span(),
+                            )
+                            .with_code(diagnostics::INVALID_INITIALIZER)
+                            .with_label("a scalar has no elements or members"),
+                        );
+                    }
+                    initialize(ty, first, offset, scalars, stack_info);
+                }
+                None => stack_info.diagnostics.push(
+                    Diagnostic::error("scalar initializer cannot be empty", *span)
+                        .with_code(diagnostics::INVALID_INITIALIZER)
+                        .with_label(format!("initializing `{}`", ty)),
+                ),
+            }
+            ty.clone()
+        }
     }
 }
 
-pub fn generate_bit_or_expr(
-    text: &mut String,
-    bit_or_expr: &BitOrExpr,
+// Initializes the array or struct `ty` at `offset` from the front of
+// `items`. With `braced`, these are the items in its own braces, whose
+// designators pick the element or member they initialize, the following
+// items continuing from there. Otherwise its braces were left out
+// (C11 6.7.9p20): it takes only the items it needs, up to the next
+// designator.
+fn initialize_list<'a>(
+    ty: &Type,
+    items: &mut Peekable<Iter<'a, (Vec<Designator>, Initializer)>>,
+    offset: i32,
+    braced: bool,
+    scalars: &mut Vec<(i32, Type, Scalar<'a>)>,
     stack_info: &mut StackInfo,
-) {
-    // <bit-or-expr> ::= <bit-xor-expr> { "|" <bit-xor-expr> }
-    generate_bit_xor_expr(text, &bit_or_expr.bit_xor_expr, stack_info);
-
-    for expr in bit_or_expr.additional.iter() {
-        text.push_str("push %rax\n");
-        generate_bit_xor_expr(text, expr, stack_info);
-        text.push_str("pop %rcx\nor %ecx, %eax\n");
+) -> Type {
+    let (mut next, mut count) = (0, 0);
+    while let Some((designators, init)) = items.peek().copied() {
+        let (index, sub_ty, sub_offset) = if designators.is_empty() {
+            let Some((sub_ty, sub_offset)) = subobject(ty, next) else {
+                break;
+            };
+            (next, sub_ty, sub_offset)
+        } else if braced {
+            items.next();
+            let Some((index, sub_ty, sub_offset)) = designate(ty, designators, stack_info) else {
+                continue;
+            };
+            // A later initializer for a subobject overrides earlier ones.
+            let (start, end) = (offset + sub_offset, offset + sub_offset + sub_ty.size());
+            scalars.retain(|(at, _, _)| *at < start || *at >= end);
+            initialize(&sub_ty, init, start, scalars, stack_info);
+            next = index + 1;
+            count = count.max(next);
+            continue;
+        } else {
+            break;
+        };
+        // An array or struct without its own braces takes as many items as
+        // it needs, except that a string can initialize a char array.
+        if !sub_ty.is_scalar()
+            && matches!(init, Initializer::Expr(_))
+            && string_initializer(&sub_ty, init).is_none()
+        {
+            initialize_list(
+                &sub_ty,
+                items,
+                offset + sub_offset,
+                false,
+                scalars,
+                stack_info,
+            );
+        } else {
+            items.next();
+            initialize(&sub_ty, init, offset + sub_offset, scalars, stack_info);
+        }
+        next = index + 1;
+        count = count.max(next);
+    }
+    match ty {
+        Type::Array(element, 0) => Type::Array(element.clone(), count),
+        _ => ty.clone(),
     }
 }
 
-pub fn generate_log_and_expr(
-    text: &mut String,
-    log_and_expr: &LogicalAndExpr,
-    stack_info: &mut StackInfo,
-) {
-    // <log-and-expr> ::= <bit-or-expr> { "&&" <bit-or-expr> }
-    generate_bit_or_expr(text, &log_and_expr.bit_or_expr, stack_info);
-
-    for expr in log_and_expr.additional.iter() {
-        let c = stack_info.counter;
-        text.push_str(
-            format!(
-                "cmpl $0, %eax\njne _clause{}\njmp _end{}\n_clause{}:\n",
-                c, c, c
-            )
-            .as_str(),
-        );
-        generate_bit_or_expr(text, expr, stack_info);
-        text.push_str(format!("cmpl $0, %eax\nmovl $0, %eax\nsetne %al\n_end{}:\n", c).as_str());
-        stack_info.counter += 1;
+// The type and offset of the `n`th element or member of `ty` initialized by
+// position, if it has one. Only the first member of a union can be.
+fn subobject(ty: &Type, n: usize) -> Option<(Type, i32)> {
+    match ty {
+        Type::Array(element, length) if *length == 0 || n < *length => {
+            Some(((**element).clone(), n as i32 * element.size()))
+        }
+        Type::Struct(aggregate) if !aggregate.union || n == 0 => aggregate
+            .members()
+            .get(n)
+            .map(|member| (member.ty.clone(), member.offset)),
+        _ => None,
     }
 }
 
-pub fn generate_log_or_expr(
-    text: &mut String,
-    log_or_expr: &LogicalOrExpr,
+// Follows the designators of an item in the braces of `ty` to the subobject
+// they name. Returns the index of the element or member of `ty` it is in,
+// and its type and offset.
+fn designate(
+    ty: &Type,
+    designators: &[Designator],
     stack_info: &mut StackInfo,
-) {
-    // <logical-or-expr> ::= <logical-and-expr> { "||" <logical-and-expr> }
-    generate_log_and_expr(text, &log_or_expr.log_and_expr, stack_info);
+) -> Option<(usize, Type, i32)> {
+    let (mut index, mut ty, mut offset) = (0, ty.clone(), 0);
+    for (i, designator) in designators.iter().enumerate() {
+        let (n, sub_ty, sub_offset) = match (&ty, designator) {
+            (Type::Array(element, length), Designator::Index(expr, span)) => {
+                let Some((n, n_ty)) = constant::eval(expr, stack_info) else {
+                    stack_info.diagnostics.push(
+                        Diagnostic::error(
+                            "array designator is not an integer constant expression",
+                            *span,
+                        )
+                        .with_code(diagnostics::NOT_CONSTANT)
+                        .with_label("not a constant expression"),
+                    );
+                    return None;
+                };
+                if (n < 0 && !n_ty.is_unsigned()) || (*length != 0 && n as u64 >= *length as u64) {
+                    stack_info.diagnostics.push(
+                        Diagnostic::error(
+                            format!("array designator index {} exceeds array bounds", n),
+                            *span,
+                        )
+                        .with_code(diagnostics::INVALID_INITIALIZER)
+                        .with_label(format!("initializing `{}`", ty)),
+                    );
+                    return None;
+                }
+                let n = n as usize;
+                (n, (**element).clone(), n as i32 * element.size())
+            }
+            (Type::Struct(aggregate), Designator::Member(name, span)) => {
+                match aggregate.find(name) {
+                    Some((n, member)) => (n, member.ty, member.offset),
+                    None => {
+                        stack_info.diagnostics.push(
+                            Diagnostic::error(
+                                format!("no member named `{}` in `{}`", name, ty),
+                                *span,
+                            )
+                            .with_code(diagnostics::NO_MEMBER)
+                            .with_label("unknown member"),
+                        );
+                        return None;
+                    }
+                }
+            }
+            (_, Designator::Index(_, span)) => {
+                stack_info.diagnostics.push(
+                    Diagnostic::error(
+                        format!("array designator used for non-array type `{}`", ty),
+                        *span,
+                    )
+                    .with_code(diagnostics::INVALID_INITIALIZER)
+                    .with_label("not an array"),
+                );
+                return None;
+            }
+            (_, Designator::Member(_, span)) => {
+                stack_info.diagnostics.push(
+                    Diagnostic::error(
+                        format!("member designator used for non-struct type `{}`", ty),
+                        *span,
+                    )
+                    .with_code(diagnostics::INVALID_INITIALIZER)
+                    .with_label("not a struct or union"),
+                );
+                return None;
+            }
+        };
+        if i == 0 {
+            index = n;
+        }
+        ty = sub_ty;
+        offset += sub_offset;
+    }
+    Some((index, ty, offset))
+}
 
-    for expr in log_or_expr.additional.iter() {
-        let c = stack_info.counter;
-        text.push_str(
-            format!(
-                "cmpl $0, %eax\nje _clause{}\nmovl $1, %eax\njmp _end{}\n_clause{}:\n",
-                c, c, c
-            )
-            .as_str(),
-        );
-        generate_log_and_expr(text, expr, stack_info);
-        text.push_str(format!("cmpl $0, %eax\nmovl $0, %eax\nsetne %al\n_end{}:\n", c).as_str());
-        stack_info.counter += 1;
+// The string literal initializing `ty`, if it is a character array.
+fn string_initializer<'a>(ty: &Type, init: &'a Initializer) -> Option<&'a Vec<u8>> {
+    let Type::Array(element, _) = ty else {
+        return None;
+    };
+    let Initializer::Expr(expr) = init else {
+        return None;
+    };
+    match expr.as_factor()? {
+        Factor::String(bytes, _) if matches!(**element, Type::Char | Type::UChar) => Some(bytes),
+        _ => None,
     }
 }
 
-pub fn generate_conditional_expr(
-    text: &mut String,
-    conditional_expr: &ConditionalExpr,
+// The values of a static variable's initializer, which must be constant.
+fn static_values(
+    scalars: &[(i32, Type, Scalar)],
     stack_info: &mut StackInfo,
-) {
-    // <conditional-expr> ::= <logical-or-expr> { "?" <expr> ":" <conditional-expr> }
-    generate_log_or_expr(text, &conditional_expr.log_or_expr, stack_info);
+) -> Vec<(i32, Type, StaticValue)> {
+    scalars
+        .iter()
+        .map(|(offset, ty, scalar)| {
+            let value = match scalar {
+                Scalar::Expr(expr) => static_initializer(expr, ty, stack_info),
+                Scalar::Constant(value) => StaticValue::Integer(*value),
+            };
+            (*offset, ty.clone(), value)
+        })
+        .collect()
+}
 
-    if let Some((a, b)) = &conditional_expr.additional {
-        let c = stack_info.counter;
-        text.push_str(format!("cmpl $0, %eax\nje _e{}\n", c).as_str());
-        generate_expr(text, a, stack_info);
-        text.push_str(format!("jmp _post_cond{}\n_e{}:\n", c, c).as_str());
-        generate_conditional_expr(text, b, stack_info);
-        text.push_str(format!("_post_cond{}:\n", c).as_str());
-        stack_info.counter += 1;
+// The value of a static variable's initializer, which must be constant,
+// converted to the variable's type. Pointers may also be initialized with an
+// address constant.
+fn static_initializer(expr: &Expression, ty: &Type, stack_info: &mut StackInfo) -> StaticValue {
+    if ty.is_pointer() {
+        if constant::is_null_pointer(expr, stack_info) {
+            return StaticValue::Integer(0);
+        }
+        if let Some((label, offset, from)) = static_address(expr, stack_info) {
+            check_conversion(&from, ty, expr, stack_info);
+            return StaticValue::Address(label, offset);
+        }
+    }
+    match constant::eval(expr, stack_info) {
+        Some(_) if ty.is_pointer() => {
+            stack_info.diagnostics.push(
+                Diagnostic::error("incompatible integer to pointer conversion", expr.span())
+                    .with_code(diagnostics::INCOMPATIBLE_TYPES)
+                    .with_label(format!("expected `{}`, found an integer", ty)),
+            );
+            StaticValue::Integer(0)
+        }
+        Some((value, _)) => StaticValue::Integer(constant::cast(value, ty)),
+        None => {
+            stack_info.diagnostics.push(
+                Diagnostic::error(
+                    "initializer element is not a compile-time constant",
+                    expr.span(),
+                )
+                .with_code(diagnostics::NOT_CONSTANT)
+                .with_label("not a constant expression"),
+            );
+            StaticValue::Integer(0)
+        }
     }
 }
 
-pub fn generate_expr(text: &mut String, expr: &Expression, stack_info: &mut StackInfo) {
-    // <expr> ::= <id> "=" <expr> | <conditional-expr>
-    match expr {
-        Expression::Assign(name, inner_expr) => {
-            generate_expr(text, inner_expr, stack_info);
-            let offset = stack_info.var_map.get(name).unwrap();
-            text.push_str(format!("movl %eax, {}(%ebp)\n", offset).as_str());
+// An address constant (C11 6.6p9): a pointer to an object with static
+// storage duration or to a string literal, plus or minus an integer
+// constant. Returns the label it is relative to, the offset in bytes and the
+// pointer type.
+fn static_address(expr: &Expression, stack_info: &mut StackInfo) -> Option<(String, i64, Type)> {
+    let Expression::Conditional(cond) = expr else {
+        return None;
+    };
+    let add = cond.as_additive()?;
+    if !add.term.additional.is_empty() {
+        return None;
+    }
+    let (label, mut offset, ty) = static_pointer(&add.term.factor, stack_info)?;
+    let size = ty.pointee()?.size() as i64;
+    for (op, term) in add.additional.iter() {
+        let (n, n_ty) = constant::eval_term(term, stack_info)?;
+        if !n_ty.is_integer() {
+            return None;
         }
-        Expression::Conditional(conditional_expr) => {
-            generate_conditional_expr(text, conditional_expr, stack_info);
+        match op {
+            TokenType::Minus => offset -= n * size,
+            _ => offset += n * size,
         }
     }
+    Some((label, offset, ty))
 }
 
-pub fn generate_statement(text: &mut String, statement: &Statement, stack_info: &mut StackInfo) {
-    // <function> :: "int" <id> "(" ")" "{" { <block-item> } "}"
-    match statement {
-        Statement::Expr(expr) => {
-            generate_expr(text, expr, stack_info);
+// The address constant a factor of pointer type evaluates to. Arrays decay
+// to the address of their first element.
+fn static_pointer(factor: &Factor, stack_info: &mut StackInfo) -> Option<(String, i64, Type)> {
+    match factor {
+        Factor::Expr(expr, _) => static_address(expr, stack_info),
+        Factor::UnaryOp(TokenType::BitAnd, target, _) => {
+            let (label, offset, ty) = static_object(target, stack_info)?;
+            Some((label, offset, Type::Pointer(Box::new(ty))))
         }
-        Statement::Return(expr) => {
-            generate_expr(text, expr, stack_info);
-            text.push_str("ret\n\n");
+        Factor::Cast(ty, inner, _) => {
+            let ty = resolve(ty, stack_info);
+            let (label, offset, _) = static_pointer(inner, stack_info)?;
+            ty.is_pointer().then_some((label, offset, ty))
         }
-        Statement::If(expr, if_state, else_state) => {
-            generate_expr(text, expr, stack_info);
-            let c = stack_info.counter;
-            text.push_str(format!("cmpl $0, %eax\nje _e{}\n", c).as_str());
-            generate_statement(text, if_state, stack_info);
+        _ => match static_object(factor, stack_info)? {
+            (label, offset, Type::Array(element, _)) => {
+                Some((label, offset, Type::Pointer(element)))
+            }
+            _ => None,
+        },
+    }
+}
 
-            match else_state {
-                Some(s) => {
-                    text.push_str(format!("jmp _post_cond{}\n_e{}:\n", c, c).as_str());
-                    generate_statement(text, s, stack_info);
-                    text.push_str(format!("_post_cond{}:\n", c).as_str());
+// The label, offset and type of the object with static storage duration, or
+// part of one, that `factor` designates.
+fn static_object(factor: &Factor, stack_info: &mut StackInfo) -> Option<(String, i64, Type)> {
+    match factor {
+        Factor::Expr(expr, _) => static_object(expr.as_factor()?, stack_info),
+        Factor::Identifier(name, _) => {
+            match stack_info
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name))?
+            {
+                (Symbol::Object(operand, ty), _) => {
+                    let label = operand.strip_suffix("(%rip)")?;
+                    Some((label.to_string(), 0, ty.clone()))
                 }
-                None => {
-                    text.push_str(format!("e_{}:", c).as_str());
+                (Symbol::Constant(_), _) => None,
+            }
+        }
+        Factor::String(bytes, _) => {
+            let label = format!(".LC{}", stack_info.strings.len());
+            stack_info.strings.push(bytes.clone());
+            let ty = Type::Array(Box::new(Type::Char), bytes.len() + 1);
+            Some((label, 0, ty))
+        }
+        Factor::UnaryOp(TokenType::Multiplication, inner, _) => {
+            let (label, offset, ty) = static_pointer(inner, stack_info)?;
+            Some((label, offset, ty.pointee()?.clone()))
+        }
+        Factor::Index(base, index, _) => {
+            let (label, offset, ty) = static_pointer(base, stack_info)?;
+            let element = ty.pointee()?.clone();
+            let (n, _) = constant::eval(index, stack_info)?;
+            Some((label, offset + n * element.size() as i64, element))
+        }
+        Factor::Member(op, base, name, _) => {
+            let (label, offset, ty) = match op {
+                TokenType::Arrow => {
+                    let (label, offset, ty) = static_pointer(base, stack_info)?;
+                    (label, offset, ty.pointee()?.clone())
                 }
+                _ => static_object(base, stack_info)?,
+            };
+            let Type::Struct(aggregate) = ty else {
+                return None;
+            };
+            let (_, member) = aggregate.find(name)?;
+            Some((label, offset + member.offset as i64, member.ty))
+        }
+        _ => None,
+    }
+}
+
+// Reports a redeclaration whose storage class contradicts the linkage given
+// by the first declaration. `extern` keeps the earlier linkage, as does no
+// storage class on a function.
+fn check_linkage(
+    name: &str,
+    storage: Option<TokenType>,
+    inherits: bool,
+    internal: bool,
+    span: Span,
+    previous: Span,
+) -> Option<Diagnostic> {
+    let message = match storage {
+        Some(TokenType::Static) if !internal => {
+            "static declaration of `{}` follows non-static declaration"
+        }
+        None if internal && !inherits => {
+            "non-static declaration of `{}` follows static declaration"
+        }
+        _ => return None,
+    };
+    Some(
+        Diagnostic::error(message.replace("{}", name), span)
+            .with_code(diagnostics::CONFLICTING_LINKAGE)
+            .with_label("conflicting storage class")
+            .with_secondary(previous, "previous declaration is here"),
+    )
+}
+
+fn different_kind(name: &str, span: Span, previous: Span) -> Diagnostic {
+    Diagnostic::error(
+        format!("`{}` redeclared as a different kind of symbol", name),
+        span,
+    )
+    .with_code(diagnostics::REDECLARATION)
+    .with_label("redeclared here")
+    .with_secondary(previous, "previous declaration is here")
+}
+
+// Records a file-scope declaration of a variable (or a block-scope `extern`
+// one), checking it against earlier ones.
+// Returns the variable's type, which an earlier declaration may have given
+// the array length of.
+fn declare_global(declaration: &Declaration, stack_info: &mut StackInfo) -> Type {
+    let name = &declaration.identifier;
+    let span = declaration.span;
+    let is_extern = declaration.storage == Some(TokenType::Extern);
+    let (ty, init) = match &declaration.init {
+        // Already reported by `generate_declaration`.
+        Some(_) if is_extern && stack_info.scopes.len() > 1 => {
+            let ty = array_type(&declaration.ty, &declaration.dims, span, stack_info);
+            (ty, None)
+        }
+        _ => {
+            let (ty, scalars) = resolve_declaration(declaration, stack_info);
+            let init = declaration
+                .init
+                .as_ref()
+                .map(|_| static_values(&scalars, stack_info));
+            (ty, init)
+        }
+    };
+
+    if let Some(function) = stack_info.functions.get(name) {
+        let diagnostic = different_kind(name, span, function.span);
+        stack_info.diagnostics.push(diagnostic);
+        return ty;
+    }
+
+    let Some(info) = stack_info.globals.get_mut(name) else {
+        let info = GlobalInfo {
+            ty: ty.clone(),
+            span,
+            defined: init.as_ref().map(|_| span),
+            init: init.unwrap_or_default(),
+            tentative: !is_extern,
+            internal: declaration.storage == Some(TokenType::Static),
+        };
+        stack_info.globals.insert(name.clone(), info);
+        stack_info.global_order.push(name.clone());
+        return ty;
+    };
+
+    let mut errors = Vec::new();
+    match (&info.ty, &ty) {
+        _ if info.ty == ty => {}
+        // An array declared without a length takes it from the other
+        // declaration.
+        (Type::Array(a, 0), Type::Array(b, _)) if a == b => info.ty = ty.clone(),
+        (Type::Array(a, _), Type::Array(b, 0)) if a == b => {}
+        _ => errors.push(
+            Diagnostic::error(format!("conflicting types for `{}`", name), span)
+                .with_code(diagnostics::CONFLICTING_TYPES)
+                .with_label(format!("declared here as `{}`", ty))
+                .with_secondary(info.span, format!("previously declared as `{}`", info.ty)),
+        ),
+    }
+    errors.extend(check_linkage(
+        name,
+        declaration.storage,
+        false,
+        info.internal,
+        span,
+        info.span,
+    ));
+    if let Some(init) = init {
+        match info.defined {
+            Some(previous) => errors.push(
+                Diagnostic::error(format!("redefinition of `{}`", name), span)
+                    .with_code(diagnostics::REDECLARATION)
+                    .with_label("redefined here")
+                    .with_secondary(previous, "previous definition is here"),
+            ),
+            None => {
+                info.defined = Some(span);
+                info.init = init;
             }
-            stack_info.counter += 1;
         }
     }
-    text.push_str("movl %ebp, %esp\npop %rbp\nret\n\n");
+    info.tentative |= !is_extern;
+    let ty = info.ty.clone();
+
+    stack_info.diagnostics.extend(errors);
+    ty
 }
 
-pub fn generate_declaration(
-    text: &mut String,
-    declaration: &Declaration,
-    stack_info: &mut StackInfo,
-) {
-    // <declaration>> ::= "int" <id> [ = <expr> ] ";"
-    let name = declaration.identifier.as_str();
+// Generates nothing for a file-scope variable until the end of the file, when
+// all its declarations are known.
+fn generate_global(declaration: &Declaration, stack_info: &mut StackInfo) {
+    let ty = declare_global(declaration, stack_info);
+    let operand = format!("{}(%rip)", declaration.identifier);
+    let entry = stack_info.scopes[0]
+        .entry(declaration.identifier.clone())
+        .or_insert((Symbol::Object(operand, ty.clone()), declaration.span));
+    match &mut entry.0 {
+        Symbol::Object(_, declared) => *declared = ty,
+        Symbol::Constant(_) => {
+            stack_info.redeclared(&declaration.identifier, declaration.span);
+        }
+    }
+}
 
-    if let Some(_) = stack_info.var_map.get(name) {
-        panic!("Variable \"{}\" already declared in this scope.", name);
+// Records a declaration or definition of `func`, checking it against earlier
+// ones.
+// Returns the types of its parameters, if it has a prototype.
+fn declare_function(func: &Function, stack_info: &mut StackInfo) -> (Type, Option<Vec<Type>>) {
+    let return_type = resolve(&func.return_type, stack_info);
+    let params: Option<Vec<Type>> = func.params.as_ref().map(|params| {
+        params
+            .iter()
+            .map(|param| param_type(param, stack_info))
+            .collect()
+    });
+    if let Some(global) = stack_info.globals.get(&func.name) {
+        let diagnostic = different_kind(&func.name, func.span, global.span);
+        stack_info.diagnostics.push(diagnostic);
+        return (return_type, params);
+    }
+    let Some(info) = stack_info.functions.get_mut(&func.name) else {
+        let info = FunctionInfo {
+            return_type: return_type.clone(),
+            params: params.clone(),
+            span: func.span,
+            defined: func.blocks.as_ref().map(|_| func.span),
+            internal: func.storage == Some(TokenType::Static),
+            implicit: false,
+        };
+        stack_info.functions.insert(func.name.clone(), info);
+        return (return_type, params);
+    };
+
+    let mut errors = Vec::new();
+    errors.extend(check_linkage(
+        &func.name,
+        func.storage,
+        true,
+        info.internal,
+        func.span,
+        info.span,
+    ));
+    // A declaration without a prototype is compatible with any parameters,
+    // and takes them from a later one that has a prototype.
+    let conflicting = match (&info.params, &params) {
+        (Some(previous), Some(params)) => *previous != *params,
+        _ => false,
+    };
+    if conflicting || (!info.implicit && info.return_type != return_type) {
+        errors.push(
+            Diagnostic::error(format!("conflicting types for `{}`", func.name), func.span)
+                .with_code(diagnostics::CONFLICTING_TYPES)
+                .with_label(format!(
+                    "declared here as `{}`",
+                    signature(&return_type, params.as_deref())
+                ))
+                .with_secondary(
+                    info.span,
+                    format!(
+                        "previously declared as `{}`",
+                        signature(&info.return_type, info.params.as_deref())
+                    ),
+                ),
+        );
+    } else if info.params.is_none() {
+        info.return_type = return_type.clone();
+        info.params = params.clone();
+        info.span = func.span;
+        info.implicit = false;
+    }
+
+    if func.blocks.is_some() {
+        match info.defined {
+            Some(previous) => errors.push(
+                Diagnostic::error(
+                    format!("redefinition of function `{}`", func.name),
+                    func.span,
+                )
+                .with_code(diagnostics::REDECLARATION)
+                .with_label("redefined here")
+                .with_secondary(previous, "previous definition is here"),
+            ),
+            None => info.defined = Some(func.span),
+        }
     }
 
-    if let Some(inner_expr) = &declaration.expr {
-        generate_expr(text, &inner_expr, stack_info);
+    stack_info.diagnostics.extend(errors);
+    (return_type, params)
+}
+
+// A function type as written in diagnostics, such as `int (char, long)`.
+fn signature(return_type: &Type, params: Option<&[Type]>) -> String {
+    let Some(params) = params else {
+        return format!("{} ()", return_type);
+    };
+    let params: Vec<String> = params.iter().map(Type::to_string).collect();
+    if params.is_empty() {
+        format!("{} (void)", return_type)
     } else {
-        // set to 0
-        text.push_str("movl $0, %eax\n");
+        format!("{} ({})", return_type, params.join(", "))
     }
+}
 
-    text.push_str("push %rax\n");
-    stack_info
-        .var_map
-        .insert(name.to_string(), stack_info.stack_index);
-    stack_info.stack_index -= 8;
+pub fn generate_function(text: &mut String, func: &Function, stack_info: &mut StackInfo) {
+    // <function> ::= <type> <id> "(" [ <type> <id> { "," <type> <id> } ] ")" "{" { <block-item> } "}"
+    let (return_type, params) = declare_function(func, stack_info);
+    let Some(blocks) = &func.blocks else {
+        return;
+    };
+    let params = params.unwrap_or_default();
+
+    let internal = stack_info.functions.get(&func.name);
+    if !internal.is_some_and(|info| info.internal) {
+        text.push_str(format!("\n.globl {}", func.name).as_str());
+    }
+    text.push_str(format!("\n{}:\n", func.name).as_str());
+    text.push_str("push %rbp\nmovq %rsp, %rbp\n");
+
+    // Each function gets a fresh frame; labels are visible throughout the
+    // function, so `goto` can jump forward.
+    stack_info.stack_index = 0;
+    stack_info.function = func.name.clone();
+    stack_info.return_type = return_type.clone();
+    stack_info.labels.clear();
+    for block in blocks.iter() {
+        if let BlockItem::Statement(s) = block {
+            collect_labels(s, stack_info);
+        }
+    }
+    reserve_return_slots(text, blocks, stack_info);
+
+    // Parameters share a scope with the outermost block of the body. Register
+    // arguments are spilled to the stack; the rest are already above the
+    // return address.
+    let stack_index = stack_info.enter_scope();
+    let registers = assign_registers(&params, &return_type);
+    stack_info.return_pointer = match classify(&return_type) {
+        Class::Memory => {
+            text.push_str("push %rdi\n");
+            stack_info.stack_index -= 8;
+            Some(format!("{}(%rbp)", stack_info.stack_index))
+        }
+        Class::Integer(_) => None,
+    };
+    let mut above = 16;
+    for ((param, ty), registers) in func.params.iter().flatten().zip(params).zip(registers) {
+        let offset = match registers {
+            Some([register]) if ty.is_scalar() => {
+                text.push_str(format!("push %{}\n", register).as_str());
+                stack_info.stack_index -= 8;
+                stack_info.stack_index
+            }
+            Some(registers) => {
+                let offset = stack_info.allocate(text, ty.size());
+                for (eightbyte, register) in registers.iter().enumerate() {
+                    text.push_str(
+                        format!(
+                            "movq %{}, {}(%rbp)\n",
+                            register,
+                            offset + 8 * eightbyte as i32
+                        )
+                        .as_str(),
+                    );
+                }
+                offset
+            }
+            None => {
+                let offset = above;
+                above += (ty.size() + 7) / 8 * 8;
+                offset
+            }
+        };
+        // The parser rejects definitions with unnamed parameters.
+        let Some(name) = &param.name else {
+            continue;
+        };
+        if !stack_info.redeclared(name, param.span) {
+            stack_info.declare(name, format!("{}(%rbp)", offset), ty, param.span);
+        }
+    }
+    generate_items(text, blocks, stack_info);
+    stack_info.exit_scope(text, stack_index);
+
+    let has_ret = matches!(
+        blocks.last(),
+        Some(BlockItem::Statement(Statement::Return(..)))
+    );
+    if !has_ret {
+        text.push_str("movl $0, %eax\nmovq %rbp, %rsp\npop %rbp\nret\n")
+    }
 }
 
-pub fn generate(prog: Program) -> String {
-    // <program> ::= <function>
-    let mut text = String::from(".globl main\n\n");
+// Generates assembly for the program, along with any errors and warnings.
+// The assembly is only usable if there are no errors.
+pub fn generate(prog: Program, standard: Standard) -> (String, Vec<Diagnostic>) {
+    // <program> ::= { <function> | <declaration> }
+    let mut text = String::new();
     let mut stack_info = StackInfo {
-        var_map: HashMap::new(),
+        scopes: Vec::new(),
+        tags: Vec::new(),
+        specifiers: HashMap::new(),
+        return_slots: HashMap::new(),
         stack_index: 0,
         counter: 0,
+        diagnostics: Vec::new(),
+        strings: Vec::new(),
+        breaks: Vec::new(),
+        continues: Vec::new(),
+        switches: Vec::new(),
+        jump_tables: String::new(),
+        function: String::new(),
+        return_type: Type::Int,
+        return_pointer: None,
+        labels: HashMap::new(),
+        functions: HashMap::new(),
+        globals: HashMap::new(),
+        global_order: Vec::new(),
+        data: Vec::new(),
+        standard,
     };
 
-    for func in prog.functions.iter() {
-        text.push_str(format!("{}:\npush %rbp\nmovl %esp, %ebp\n", func.name).as_str());
-
-        let mut has_ret: bool = false;
-
-        for block in func.blocks.iter() {
-            match block {
-                BlockItem::Statement(s) => {
-                    if let Statement::Return(_) = s {
-                        has_ret = true;
-                    }
-                    generate_statement(&mut text, s, &mut stack_info);
+    // File scope, below every function's scopes.
+    stack_info.enter_scope();
+    for item in prog.items.iter() {
+        match item {
+            TopLevel::Function(func) => generate_function(&mut text, func, &mut stack_info),
+            TopLevel::Declaration(declarations) => {
+                for d in declarations {
+                    generate_global(d, &mut stack_info);
                 }
-                BlockItem::Declaration(d) => generate_declaration(&mut text, d, &mut stack_info),
             }
+            TopLevel::Tag(ty, _) => generate_tag(ty, &mut stack_info),
+        }
+    }
+
+    // Tentative definitions without an initializer are zero.
+    for name in stack_info.global_order.iter() {
+        let info = &stack_info.globals[name];
+        if info.defined.is_some() || info.tentative {
+            stack_info.data.push(StaticData {
+                label: name.clone(),
+                ty: info.ty.clone(),
+                init: info.init.clone(),
+                global: !info.internal,
+            });
+        }
+    }
+    for data in stack_info.data.iter() {
+        if data.global {
+            text.push_str(format!("\n.globl {}", data.label).as_str());
+        }
+        let (size, align) = (data.ty.size(), data.ty.align());
+        if data
+            .init
+            .iter()
+            .all(|(_, _, value)| *value == StaticValue::Integer(0))
+        {
+            text.push_str(
+                format!(
+                    "\n.bss\n.align {}\n{}:\n.zero {}\n",
+                    align, data.label, size
+                )
+                .as_str(),
+            );
+            continue;
+        }
+        text.push_str(format!("\n.data\n.align {}\n{}:\n", align, data.label).as_str());
+        // Fill the gaps between the initialized scalars with zeros.
+        let mut init: Vec<&(i32, Type, StaticValue)> = data.init.iter().collect();
+        init.sort_by_key(|(offset, _, _)| *offset);
+        let mut end = 0;
+        for (offset, ty, value) in init {
+            if *offset > end {
+                text.push_str(format!(".zero {}\n", offset - end).as_str());
+            }
+            let directive = match ty.size() {
+                1 => ".byte",
+                2 => ".short",
+                4 => ".long",
+                _ => ".quad",
+            };
+            text.push_str(format!("{} {}\n", directive, value).as_str());
+            end = offset + ty.size();
+        }
+        if size > end {
+            text.push_str(format!(".zero {}\n", size - end).as_str());
         }
+    }
 
-        if !has_ret {
-            text.push_str("movl $0, %eax\nret\n")
+    if !stack_info.strings.is_empty() {
+        text.push_str("\n.section .rodata\n");
+        for (i, bytes) in stack_info.strings.iter().enumerate() {
+            text.push_str(format!(".LC{}:\n.string \"{}\"\n", i, escape_asm(bytes)).as_str());
         }
     }
 
-    text
+    if !stack_info.jump_tables.is_empty() {
+        text.push_str("\n.section .rodata\n.align 4\n");
+        text.push_str(&stack_info.jump_tables);
+    }
+
+    // Mark the stack as non-executable.
+    text.push_str("\n.section .note.GNU-stack,\"\",@progbits\n");
+
+    (text, stack_info.diagnostics)
+}
+
+// Escapes bytes for a GNU as `.string` directive.
+fn escape_asm(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for b in bytes.iter() {
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            0x20..=0x7e => out.push(*b as char),
+            _ => out.push_str(format!("\\{:03o}", b).as_str()),
+        }
+    }
+    out
 }
 
 pub fn write_asm(path: &str, text: &str) {
     std::fs::write(format!("{}.s", path), text).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer;
+
+    fn compile(source: &str) -> String {
+        let (tokens, _) = lexer::lex(source, 0);
+        let (asm, diagnostics) = generate(parse(&tokens).unwrap(), Standard::C17);
+        assert!(diagnostics.is_empty());
+        asm
+    }
+
+    #[test]
+    fn emits_string_literals_into_rodata() {
+        let asm = compile(r#"int main() { "say \"hi\"" "\x41\n"; "\0\\"; return 0; }"#);
+        assert!(asm.contains("leaq .LC0(%rip), %rax\n"));
+        assert!(asm.contains("leaq .LC1(%rip), %rax\n"));
+        assert!(asm.contains(
+            ".section .rodata\n.LC0:\n.string \"say \\\"hi\\\"A\\012\"\n.LC1:\n.string \"\\000\\\\\"\n"
+        ));
+    }
+}
@@ -1,5 +1,5 @@
-use fancy_regex::Regex;
-use std::collections::HashSet;
+use crate::diagnostics::{self, Diagnostic};
+use crate::span::Span;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum TokenType {
@@ -7,6 +7,8 @@ pub enum TokenType {
     RBrace,
     LParen,
     RParen,
+    LBracket,
+    RBracket,
     Semicolon,
     Integer,
     Return,
@@ -43,177 +45,1049 @@ pub enum TokenType {
     AssignBitAnd,
     AssignBitOr,
     AssignBitXOr,
+    Increment,
+    Decrement,
     If,
     Else,
+    While,
+    Do,
+    For,
+    Break,
+    Continue,
+    Switch,
+    Case,
+    Default,
+    Goto,
+    Void,
+    Char,
+    Short,
+    Long,
+    Signed,
+    Unsigned,
+    Static,
+    Extern,
+    Sizeof,
+    Struct,
+    Union,
+    Enum,
     Colon,
     QuestionMark,
+    Comma,
+    Dot,
+    Arrow,
+    CharLiteral,
+    StringLiteral,
+    // A character that can't start any token; always reported by the lexer.
+    Error,
+    Eof,
+}
+
+// The type of an integer constant, chosen from its suffix and value as in
+// C11 6.4.4.1.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum IntKind {
+    Int,
+    UInt,
+    Long,
+    ULong,
+    LongLong,
+    ULongLong,
+}
+
+impl IntKind {
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, IntKind::UInt | IntKind::ULong | IntKind::ULongLong)
+    }
+
+    fn max(&self) -> u64 {
+        match self {
+            IntKind::Int => i32::MAX as u64,
+            IntKind::UInt => u32::MAX as u64,
+            IntKind::Long | IntKind::LongLong => i64::MAX as u64,
+            IntKind::ULong | IntKind::ULongLong => u64::MAX,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct IntLiteral {
+    pub value: u64,
+    pub kind: IntKind,
+    // A decimal constant without a `u` suffix that only fits an unsigned type.
+    pub implicitly_unsigned: bool,
+}
+
+#[derive(Debug)]
+pub enum LiteralError {
+    Invalid(String),
+    TooLarge(String),
+}
+
+// Decodes an integer constant such as `42`, `0x1F`, `0755`, `0b101` or
+// `10ull`.
+pub fn int_literal(text: &str) -> Result<IntLiteral, LiteralError> {
+    let lower = text.to_ascii_lowercase();
+    let (radix, digits_start) = if lower.starts_with("0x") {
+        (16, 2)
+    } else if lower.starts_with("0b") {
+        (2, 2)
+    } else if text.starts_with('0') {
+        (8, 1)
+    } else {
+        (10, 0)
+    };
+
+    let digits_end = text[digits_start..]
+        .find(|c: char| !c.is_ascii_hexdigit() || (radix != 16 && c.is_ascii_alphabetic()))
+        .map_or(text.len(), |i| i + digits_start);
+    let (digits, suffix) = (&text[digits_start..digits_end], &text[digits_end..]);
+
+    if digits.is_empty() && radix != 8 {
+        return Err(LiteralError::Invalid(format!(
+            "integer constant `{}` has no digits",
+            text
+        )));
+    }
+
+    let mut value: u64 = 0;
+    let mut overflow = false;
+    for c in digits.chars() {
+        let digit = match c.to_digit(radix) {
+            Some(digit) => digit,
+            None => {
+                let base = if radix == 8 { "octal" } else { "binary" };
+                return Err(LiteralError::Invalid(format!(
+                    "invalid digit '{}' in {} constant",
+                    c, base
+                )));
+            }
+        };
+        match value
+            .checked_mul(radix as u64)
+            .and_then(|v| v.checked_add(digit as u64))
+        {
+            Some(v) => value = v,
+            None => overflow = true,
+        }
+    }
+
+    let (unsigned, long) = match suffix.to_ascii_lowercase().as_str() {
+        _ if suffix.contains("lL") || suffix.contains("Ll") => (false, 3),
+        "" => (false, 0),
+        "u" => (true, 0),
+        "l" => (false, 1),
+        "ul" | "lu" => (true, 1),
+        "ll" => (false, 2),
+        "ull" | "llu" => (true, 2),
+        _ => (false, 3),
+    };
+    if long == 3 {
+        return Err(LiteralError::Invalid(format!(
+            "invalid suffix `{}` on integer constant",
+            suffix
+        )));
+    }
+    if overflow {
+        return Err(LiteralError::TooLarge(format!(
+            "integer constant `{}` is too large for any integer type",
+            text
+        )));
+    }
+
+    let candidates: &[IntKind] = match (unsigned, long, radix == 10) {
+        (false, 0, true) => &[IntKind::Int, IntKind::Long, IntKind::LongLong],
+        (false, 0, false) => &[
+            IntKind::Int,
+            IntKind::UInt,
+            IntKind::Long,
+            IntKind::ULong,
+            IntKind::LongLong,
+            IntKind::ULongLong,
+        ],
+        (true, 0, _) => &[IntKind::UInt, IntKind::ULong, IntKind::ULongLong],
+        (false, 1, true) => &[IntKind::Long, IntKind::LongLong],
+        (false, 1, false) => &[
+            IntKind::Long,
+            IntKind::ULong,
+            IntKind::LongLong,
+            IntKind::ULongLong,
+        ],
+        (true, 1, _) => &[IntKind::ULong, IntKind::ULongLong],
+        (false, _, true) => &[IntKind::LongLong],
+        (false, _, false) => &[IntKind::LongLong, IntKind::ULongLong],
+        (true, _, _) => &[IntKind::ULongLong],
+    };
+
+    match candidates.iter().find(|kind| value <= kind.max()) {
+        Some(kind) => Ok(IntLiteral {
+            value,
+            kind: *kind,
+            implicitly_unsigned: false,
+        }),
+        // Like gcc and clang, fall back to the largest unsigned type rather
+        // than rejecting the constant outright.
+        None => Ok(IntLiteral {
+            value,
+            kind: IntKind::ULongLong,
+            implicitly_unsigned: true,
+        }),
+    }
+}
+
+// A problem with an escape sequence, `offset..offset + len` bytes into the
+// literal's body.
+#[derive(Debug)]
+pub struct EscapeError {
+    pub offset: usize,
+    pub len: usize,
+    pub message: String,
+    pub is_error: bool,
+}
+
+// Decodes the body of a character or string literal (without its quotes),
+// resolving `\n`, `\x41`, `\0`, `\101` and the other C escape sequences.
+pub fn unescape(body: &str) -> (Vec<u8>, Vec<EscapeError>) {
+    let bytes = body.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut errors = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            out.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        i += 1;
+        let c = bytes.get(i).copied().unwrap_or(b'\\');
+        i += 1;
+        let byte = match c {
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'r' => b'\r',
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'v' => 0x0b,
+            b'\\' | b'\'' | b'"' | b'?' => c,
+            b'0'..=b'7' => {
+                let mut value = (c - b'0') as u32;
+                let mut count = 1;
+                while count < 3 && i < bytes.len() && (b'0'..=b'7').contains(&bytes[i]) {
+                    value = value * 8 + (bytes[i] - b'0') as u32;
+                    i += 1;
+                    count += 1;
+                }
+                if value > 0xff {
+                    errors.push(EscapeError {
+                        offset: start,
+                        len: i - start,
+                        message: String::from("octal escape sequence out of range"),
+                        is_error: true,
+                    });
+                }
+                value as u8
+            }
+            b'x' => {
+                let digits_start = i;
+                let mut value: u32 = 0;
+                while i < bytes.len() && bytes[i].is_ascii_hexdigit() {
+                    value = value
+                        .saturating_mul(16)
+                        .saturating_add((bytes[i] as char).to_digit(16).unwrap_or(0));
+                    i += 1;
+                }
+                if i == digits_start {
+                    errors.push(EscapeError {
+                        offset: start,
+                        len: i - start,
+                        message: String::from("`\\x` used with no following hex digits"),
+                        is_error: true,
+                    });
+                } else if value > 0xff {
+                    errors.push(EscapeError {
+                        offset: start,
+                        len: i - start,
+                        message: String::from("hex escape sequence out of range"),
+                        is_error: true,
+                    });
+                }
+                value as u8
+            }
+            _ => {
+                // Keep the character itself, as gcc and clang do.
+                let len = body[start + 1..].chars().next().map_or(1, char::len_utf8);
+                i = start + 1 + len;
+                errors.push(EscapeError {
+                    offset: start,
+                    len: 1 + len,
+                    message: format!("unknown escape sequence `{}`", &body[start..i]),
+                    is_error: false,
+                });
+                out.extend_from_slice(&bytes[start + 1..i]);
+                continue;
+            }
+        };
+        out.push(byte);
+    }
+
+    (out, errors)
+}
+
+// Value of a character constant. A single character is a `char` converted to
+// `int`, so it is sign-extended; multi-character constants pack their bytes
+// like gcc does.
+pub fn char_value(bytes: &[u8]) -> i32 {
+    match bytes {
+        [b] => *b as i8 as i32,
+        _ => bytes
+            .iter()
+            .fold(0i32, |acc, b| acc.wrapping_shl(8) | *b as i32),
+    }
 }
 
 #[derive(Debug)]
 pub struct Token {
     pub text: String,
     pub token_type: TokenType,
-    pub start: u64,
-    pub end: u64,
+    pub span: Span,
 }
 
-// assuming that intervals are inclusive!
-fn in_interval_set(interval: (u64, u64), intervals: &Vec<(u64, u64)>) -> bool {
-    for int in intervals.iter() {
-        if interval.0 <= int.1 && interval.1 >= int.0 {
-            return true;
+// Punctuators ordered longest first so that the first match is the maximal munch.
+const PUNCTUATORS: &[(&str, TokenType)] = &[
+    ("<<=", TokenType::AssignLBitShift),
+    (">>=", TokenType::AssignRBitShift),
+    ("==", TokenType::Equal),
+    ("!=", TokenType::NotEqual),
+    ("<=", TokenType::LessThanEqual),
+    (">=", TokenType::GreaterThanEqual),
+    ("&&", TokenType::And),
+    ("||", TokenType::Or),
+    ("<<", TokenType::LBitShift),
+    (">>", TokenType::RBitShift),
+    ("++", TokenType::Increment),
+    ("--", TokenType::Decrement),
+    ("->", TokenType::Arrow),
+    ("+=", TokenType::AssignPlus),
+    ("-=", TokenType::AssignMinus),
+    ("*=", TokenType::AssignMult),
+    ("/=", TokenType::AssignDivide),
+    ("%=", TokenType::AssignMod),
+    ("&=", TokenType::AssignBitAnd),
+    ("|=", TokenType::AssignBitOr),
+    ("^=", TokenType::AssignBitXOr),
+    ("{", TokenType::LBrace),
+    ("}", TokenType::RBrace),
+    ("(", TokenType::LParen),
+    (")", TokenType::RParen),
+    ("[", TokenType::LBracket),
+    ("]", TokenType::RBracket),
+    (";", TokenType::Semicolon),
+    (",", TokenType::Comma),
+    (".", TokenType::Dot),
+    (":", TokenType::Colon),
+    ("?", TokenType::QuestionMark),
+    ("~", TokenType::BitComplement),
+    ("!", TokenType::LogicalNeg),
+    ("+", TokenType::Addition),
+    ("-", TokenType::Minus),
+    ("*", TokenType::Multiplication),
+    ("/", TokenType::Division),
+    ("%", TokenType::Modulo),
+    ("=", TokenType::Assign),
+    ("<", TokenType::LessThan),
+    (">", TokenType::GreaterThan),
+    ("&", TokenType::BitAnd),
+    ("|", TokenType::BitOr),
+    ("^", TokenType::BitXOr),
+];
+
+fn keyword(text: &str) -> Option<TokenType> {
+    match text {
+        "int" => Some(TokenType::Integer),
+        "return" => Some(TokenType::Return),
+        "if" => Some(TokenType::If),
+        "else" => Some(TokenType::Else),
+        "while" => Some(TokenType::While),
+        "do" => Some(TokenType::Do),
+        "for" => Some(TokenType::For),
+        "break" => Some(TokenType::Break),
+        "continue" => Some(TokenType::Continue),
+        "switch" => Some(TokenType::Switch),
+        "case" => Some(TokenType::Case),
+        "default" => Some(TokenType::Default),
+        "goto" => Some(TokenType::Goto),
+        "void" => Some(TokenType::Void),
+        "char" => Some(TokenType::Char),
+        "short" => Some(TokenType::Short),
+        "long" => Some(TokenType::Long),
+        "signed" => Some(TokenType::Signed),
+        "unsigned" => Some(TokenType::Unsigned),
+        "static" => Some(TokenType::Static),
+        "extern" => Some(TokenType::Extern),
+        "sizeof" => Some(TokenType::Sizeof),
+        "struct" => Some(TokenType::Struct),
+        "union" => Some(TokenType::Union),
+        "enum" => Some(TokenType::Enum),
+        _ => None,
+    }
+}
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+fn is_ident_continue(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+struct Lexer<'a> {
+    file: &'a str,
+    bytes: &'a [u8],
+    file_id: usize,
+    pos: usize,
+    line: u32,
+    line_start: usize,
+    // Last computed column, so that long lines are only walked once.
+    col_mark: (usize, u32),
+    tokens: Vec<Token>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Lexer<'_> {
+    // Span of `start..end`, where `start` is on the current line.
+    fn span(&mut self, start: usize, end: usize) -> Span {
+        let (mark, mark_col) = self.col_mark;
+        let col = if mark >= self.line_start && mark <= start {
+            mark_col + self.file[mark..start].chars().count() as u32
+        } else {
+            self.file[self.line_start..start].chars().count() as u32 + 1
+        };
+        self.col_mark = (start, col);
+
+        Span {
+            file: self.file_id,
+            start,
+            end,
+            line: self.line,
+            col,
+        }
+    }
+
+    fn peek(&self, offset: usize) -> u8 {
+        self.bytes.get(self.pos + offset).copied().unwrap_or(0)
+    }
+
+    // Advances one byte, keeping track of line starts.
+    fn bump(&mut self) {
+        if self.bytes[self.pos] == b'\n' {
+            self.line += 1;
+            self.line_start = self.pos + 1;
         }
+        self.pos += 1;
     }
 
-    false
-}
-
-// fn merge_intervals(interval: (u64, u64), intervals: &mut Vec<(u64, u64)>) {
-// three cases
-// 1. no overlap, interval is just inserted
-// 2. 1 overlap, one interval is merged with
-// 3. 2 overlaps, two intervals and additional interval are merged into one
-// for i in 0..intervals.len() {
-//     if intervals[i]
-// }
-// }
-
-pub fn lex(file: &str) -> Vec<Token> {
-    // Somewhat inefficient, but it works well enough
-    let patterns = [
-        r"\{",
-        r"\}",
-        r"\(",
-        r"\)",
-        r";",
-        r":",
-        r"\?",
-        r"int(?=[\s(]+)",
-        r"return(?=[\s;]+)",
-        r"if(?=[\s(]+)",
-        r"else(?=[\s]+)",
-        r"[a-zA-Z_]\w*",
-        r"[0-9]+",
-        r"==",
-        r"<=",
-        r">=",
-        r"!=",
-        r"\+=",
-        r"-=",
-        r"\*=",
-        r"/=",
-        r"%=",
-        r"<<=",
-        r">>=",
-        r"&=",
-        r"\|=",
-        r"^=",
-        r"~",
-        r"!",
-        r"\+",
-        r"-",
-        r"\*",
-        r"/",
-        r"%",
-        r"<<",
-        r">>",
-        r"&&",
-        r"\|\|",
-        r"=",
-        r"<",
-        r">",
-        r"&",
-        r"\|",
-        r"\^",
-    ];
-
-    let mut token_indices: HashSet<u64> = HashSet::new();
-    let mut tokens: Vec<Token> = Vec::new();
-
-    for pattern in patterns.iter() {
-        let re = Regex::new(&pattern).unwrap();
-        let matches: Vec<fancy_regex::Match<'_>> = re
-            .find_iter(file)
-            .filter(|m| {
-                let tmp = m.as_ref().unwrap();
-                for i in tmp.start()..tmp.end() {
-                    if token_indices.contains(&(i as u64)) {
-                        return false;
-                    }
+    fn push(&mut self, token_type: TokenType, start: usize) {
+        let span = self.span(start, self.pos);
+        self.tokens.push(Token {
+            text: self.file[start..self.pos].to_string(),
+            token_type,
+            span,
+        });
+    }
+
+    fn number(&mut self, start: usize) -> TokenType {
+        let text = &self.file[start..self.pos];
+        match int_literal(text) {
+            Ok(literal) => {
+                if literal.implicitly_unsigned {
+                    let span = self.span(start, self.pos);
+                    self.diagnostics.push(
+                        Diagnostic::warning(
+                            "integer constant is too large for a signed type, treating it as `unsigned long long`",
+                            span,
+                        )
+                        .with_code(diagnostics::IMPLICITLY_UNSIGNED)
+                        .with_suggestion(
+                            Span { start: self.pos, col: span.col + text.chars().count() as u32, ..span },
+                            "u",
+                            "make it explicitly unsigned with",
+                        ),
+                    );
                 }
-                true
-            })
-            .map(|m| m.unwrap())
-            .collect();
+                TokenType::Literal
+            }
+            Err(err) => {
+                let span = self.span(start, self.pos);
+                let (code, message) = match err {
+                    LiteralError::Invalid(message) => (diagnostics::INVALID_LITERAL, message),
+                    LiteralError::TooLarge(message) => (diagnostics::LITERAL_TOO_LARGE, message),
+                };
+                self.diagnostics
+                    .push(Diagnostic::error(message, span).with_code(code));
+                TokenType::Error
+            }
+        }
+    }
 
-        for m in matches.iter() {
-            let text = m.as_str();
+    // Scans a quoted literal starting at `start` and reports bad escapes. An
+    // unterminated literal produces an error token.
+    fn quoted(&mut self, start: usize, quote: u8) -> TokenType {
+        self.pos += 1;
+        loop {
+            if self.pos >= self.bytes.len() || self.bytes[self.pos] == b'\n' {
+                let (what, close) = if quote == b'"' {
+                    ("string literal", "\"")
+                } else {
+                    ("character constant", "'")
+                };
+                let span = self.span(start, self.pos);
+                let end = self.span(self.pos, self.pos);
+                self.diagnostics.push(
+                    Diagnostic::error(format!("unterminated {}", what), span)
+                        .with_code(diagnostics::UNTERMINATED_LITERAL)
+                        .with_suggestion(end, close, "close it with"),
+                );
+                return TokenType::Error;
+            }
 
-            // TODO: Very inefficient, should just implement an intervals!
-            for i in m.start()..m.end() {
-                token_indices.insert(i as u64);
+            match self.bytes[self.pos] {
+                b'\\' if !matches!(self.peek(1), b'\n' | 0) => self.pos += 2,
+                c if c == quote => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => self.pos += 1,
             }
+        }
+
+        let body = &self.file[start + 1..self.pos - 1];
+        let (bytes, errors) = unescape(body);
+        let mut failed = false;
+        for err in errors {
+            let at = start + 1 + err.offset;
+            let span = self.span(at, at + err.len);
+            failed |= err.is_error;
+            let diagnostic = if err.is_error {
+                Diagnostic::error(err.message, span).with_code(diagnostics::INVALID_ESCAPE)
+            } else {
+                Diagnostic::warning(err.message, span).with_code(diagnostics::UNKNOWN_ESCAPE)
+            };
+            self.diagnostics.push(diagnostic);
+        }
 
-            let mut tk = Token {
-                text: text.to_string(),
-                token_type: match text {
-                    "{" => TokenType::LBrace,
-                    "}" => TokenType::RBrace,
-                    "(" => TokenType::LParen,
-                    ")" => TokenType::RParen,
-                    ";" => TokenType::Semicolon,
-                    "int" => TokenType::Integer,
-                    "return" => TokenType::Return,
-                    "~" => TokenType::BitComplement,
-                    "!" => TokenType::LogicalNeg,
-                    "+" => TokenType::Addition,
-                    "-" => TokenType::Minus,
-                    "*" => TokenType::Multiplication,
-                    "/" => TokenType::Division,
-                    "%" => TokenType::Modulo,
-                    "&&" => TokenType::And,
-                    "||" => TokenType::Or,
-                    "==" => TokenType::Equal,
-                    "!=" => TokenType::NotEqual,
-                    "<" => TokenType::LessThan,
-                    "<=" => TokenType::LessThanEqual,
-                    ">" => TokenType::GreaterThan,
-                    ">=" => TokenType::GreaterThanEqual,
-                    "&" => TokenType::BitAnd,
-                    "|" => TokenType::BitOr,
-                    "^" => TokenType::BitXOr,
-                    "<<" => TokenType::LBitShift,
-                    ">>" => TokenType::RBitShift,
-                    "=" => TokenType::Assign,
-                    "+=" => TokenType::AssignPlus,
-                    "-=" => TokenType::AssignMinus,
-                    "*=" => TokenType::AssignMult,
-                    "/=" => TokenType::AssignDivide,
-                    "%=" => TokenType::AssignMod,
-                    "<<=" => TokenType::AssignLBitShift,
-                    ">>=" => TokenType::AssignRBitShift,
-                    "&=" => TokenType::AssignBitAnd,
-                    "|=" => TokenType::AssignBitOr,
-                    "^=" => TokenType::AssignBitXOr,
-                    "if" => TokenType::If,
-                    "else" => TokenType::Else,
-                    "?" => TokenType::QuestionMark,
-                    ":" => TokenType::Colon,
-                    _ => TokenType::Identifier,
-                },
-                start: m.start() as u64,
-                end: m.end() as u64,
+        if quote == b'"' {
+            return if failed {
+                TokenType::Error
+            } else {
+                TokenType::StringLiteral
             };
+        }
 
-            if text.chars().all(char::is_numeric) {
-                tk.token_type = TokenType::Literal;
+        let span = self.span(start, self.pos);
+        match bytes.len() {
+            0 => {
+                self.diagnostics.push(
+                    Diagnostic::error("empty character constant", span)
+                        .with_code(diagnostics::INVALID_LITERAL),
+                );
+                TokenType::Error
             }
+            1 => TokenType::CharLiteral,
+            n => {
+                if n > 4 {
+                    self.diagnostics.push(
+                        Diagnostic::error("character constant too long for its type", span)
+                            .with_code(diagnostics::INVALID_LITERAL),
+                    );
+                    return TokenType::Error;
+                }
+                self.diagnostics.push(
+                    Diagnostic::warning("multi-character character constant", span)
+                        .with_code(diagnostics::MULTI_CHAR_CONSTANT),
+                );
+                if failed {
+                    TokenType::Error
+                } else {
+                    TokenType::CharLiteral
+                }
+            }
+        }
+    }
 
-            tokens.push(tk);
+    fn line_comment(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'\n' {
+            self.pos += 1;
         }
     }
 
-    tokens.sort_by(|a, b| a.start.cmp(&b.start));
+    // Block comments end at the first `*/`; a `/*` inside one doesn't nest.
+    fn block_comment(&mut self) {
+        let start = self.pos;
+        let open = self.span(start, start + 2);
+        self.pos += 2;
+
+        while self.pos < self.bytes.len() {
+            if self.peek(0) == b'*' && self.peek(1) == b'/' {
+                self.pos += 2;
+                return;
+            }
+            if self.peek(0) == b'/' && self.peek(1) == b'*' {
+                let nested = self.span(self.pos, self.pos + 2);
+                self.diagnostics.push(
+                    Diagnostic::warning("`/*` within block comment", nested)
+                        .with_code(diagnostics::NESTED_COMMENT)
+                        .with_secondary(open, "comment starts here")
+                        .with_note(
+                            "block comments do not nest; the comment ends at the first `*/`",
+                        ),
+                );
+            }
+            self.bump();
+        }
+
+        let end = self.span(self.pos, self.pos);
+        self.diagnostics.push(
+            Diagnostic::error("unterminated block comment", open)
+                .with_code(diagnostics::UNTERMINATED_COMMENT)
+                .with_label("comment starts here")
+                .with_suggestion(end, "*/", "close the comment with"),
+        );
+    }
+}
+
+// Scans the file once from left to right, always taking the longest token that
+// matches at the current position. Comments and whitespace are skipped.
+pub fn lex(file: &str, file_id: usize) -> (Vec<Token>, Vec<Diagnostic>) {
+    let mut lexer = Lexer {
+        file,
+        bytes: file.as_bytes(),
+        file_id,
+        pos: 0,
+        line: 1,
+        line_start: 0,
+        col_mark: (0, 1),
+        tokens: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let bytes = file.as_bytes();
+
+    while lexer.pos < bytes.len() {
+        let b = bytes[lexer.pos];
+        let start = lexer.pos;
+
+        // C white space includes vertical tab, which `is_ascii_whitespace`
+        // leaves out.
+        let token_type = if b.is_ascii_whitespace() || b == b'\x0b' {
+            lexer.bump();
+            continue;
+        } else if b == b'/' && lexer.peek(1) == b'/' {
+            lexer.line_comment();
+            continue;
+        } else if b == b'/' && lexer.peek(1) == b'*' {
+            lexer.block_comment();
+            continue;
+        } else if b == b'\'' || b == b'"' {
+            lexer.quoted(start, b)
+        } else if is_ident_start(b) {
+            while lexer.pos < bytes.len() && is_ident_continue(bytes[lexer.pos]) {
+                lexer.pos += 1;
+            }
+            keyword(&file[start..lexer.pos]).unwrap_or(TokenType::Identifier)
+        } else if b.is_ascii_digit() {
+            // Take the whole preprocessing number so bad digits and suffixes
+            // are reported instead of being split into separate tokens.
+            while lexer.pos < bytes.len() && is_ident_continue(bytes[lexer.pos]) {
+                lexer.pos += 1;
+            }
+            lexer.number(start)
+        } else if let Some((text, tt)) = PUNCTUATORS
+            .iter()
+            .find(|(p, _)| bytes[lexer.pos..].starts_with(p.as_bytes()))
+        {
+            lexer.pos += text.len();
+            *tt
+        } else {
+            let c = file[lexer.pos..].chars().next().unwrap_or('\0');
+            lexer.pos += c.len_utf8();
+            let span = lexer.span(start, lexer.pos);
+            lexer.diagnostics.push(
+                Diagnostic::error(format!("unknown character {:?}", c), span)
+                    .with_code(diagnostics::UNKNOWN_CHARACTER)
+                    .with_label("not valid in C source"),
+            );
+            TokenType::Error
+        };
+
+        lexer.push(token_type, start);
+    }
+
+    lexer.push(TokenType::Eof, file.len());
+
+    (lexer.tokens, lexer.diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The tokens of `source` without the trailing `Eof`, as `(type, text)`.
+    fn tokens(source: &str) -> Vec<(TokenType, String)> {
+        let (tokens, _) = lex(source, 0);
+        tokens
+            .into_iter()
+            .filter(|token| token.token_type != TokenType::Eof)
+            .map(|token| (token.token_type, token.text))
+            .collect()
+    }
+
+    fn types(source: &str) -> Vec<TokenType> {
+        tokens(source).into_iter().map(|(tt, _)| tt).collect()
+    }
+
+    // The diagnostics for `source`, as `<code> <line>:<column> <message>`.
+    fn diagnostics(source: &str) -> Vec<String> {
+        let (_, diagnostics) = lex(source, 0);
+        diagnostics
+            .iter()
+            .map(|d| {
+                format!(
+                    "{} {}:{} {}",
+                    d.code.unwrap_or(""),
+                    d.span.line,
+                    d.span.col,
+                    d.message
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_tokens_without_whitespace() {
+        use TokenType::*;
+        assert_eq!(
+            types("int*p;return(x);"),
+            [
+                Integer,
+                Multiplication,
+                Identifier,
+                Semicolon,
+                Return,
+                LParen,
+                Identifier,
+                RParen,
+                Semicolon
+            ]
+        );
+        assert_eq!(
+            tokens("int main(){return 2;}")
+                .into_iter()
+                .map(|(_, text)| text)
+                .collect::<Vec<_>>(),
+            ["int", "main", "(", ")", "{", "return", "2", ";", "}"]
+        );
+    }
+
+    #[test]
+    fn takes_the_longest_punctuator() {
+        use TokenType::*;
+        assert_eq!(
+            types("a<<=b>>c&&d||e-=f!=g<h"),
+            [
+                Identifier,
+                AssignLBitShift,
+                Identifier,
+                RBitShift,
+                Identifier,
+                And,
+                Identifier,
+                Or,
+                Identifier,
+                AssignMinus,
+                Identifier,
+                NotEqual,
+                Identifier,
+                LessThan,
+                Identifier,
+            ]
+        );
+        assert_eq!(
+            types("a>>=b>=c"),
+            [
+                Identifier,
+                AssignRBitShift,
+                Identifier,
+                GreaterThanEqual,
+                Identifier
+            ]
+        );
+        assert_eq!(
+            types("p->x-->y.z"),
+            [
+                Identifier,
+                Arrow,
+                Identifier,
+                Decrement,
+                GreaterThan,
+                Identifier,
+                Dot,
+                Identifier
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_increments_greedily() {
+        use TokenType::*;
+        assert_eq!(
+            types("a+++b"),
+            [Identifier, Increment, Addition, Identifier]
+        );
+        assert_eq!(types("a---b"), [Identifier, Decrement, Minus, Identifier]);
+        assert_eq!(
+            types("a+ ++b"),
+            [Identifier, Addition, Increment, Identifier]
+        );
+        assert_eq!(
+            types("--a-=1"),
+            [Decrement, Identifier, AssignMinus, Literal]
+        );
+    }
 
-    tokens
+    #[test]
+    fn skips_every_kind_of_white_space() {
+        use TokenType::*;
+        let source = "int\tx\x0b=\x0c1\r\n;";
+        assert_eq!(
+            types(source),
+            [Integer, Identifier, Assign, Literal, Semicolon]
+        );
+        assert_eq!(diagnostics(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn keywords_need_a_word_boundary() {
+        use TokenType::*;
+        assert_eq!(
+            types("returnx return_ int1 intx int"),
+            [Identifier, Identifier, Identifier, Identifier, Integer]
+        );
+    }
+
+    #[test]
+    fn records_line_and_column() {
+        let (tokens, _) = lex("int main() {\n  return 0;\n}\n", 0);
+        let positions: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.span.line, token.span.col))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (1, 1),
+                (1, 5),
+                (1, 9),
+                (1, 10),
+                (1, 12),
+                (2, 3),
+                (2, 10),
+                (2, 11),
+                (3, 1),
+                (4, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_comments() {
+        use TokenType::*;
+        let source = "\
+int /* a
+block */ x; // to the end of the line
+x/**/y // comment */
+a//b
+/* * / */ b
+";
+        assert_eq!(
+            types(source),
+            [Integer, Identifier, Semicolon, Identifier, Identifier, Identifier, Identifier]
+        );
+        assert_eq!(diagnostics(source), Vec::<String>::new());
+        assert_eq!(types("a / b"), [Identifier, Division, Identifier]);
+    }
+
+    #[test]
+    fn block_comments_do_not_nest() {
+        use TokenType::*;
+        let source = "a /* outer /* inner */ b */";
+        assert_eq!(
+            types(source),
+            [Identifier, Identifier, Multiplication, Division]
+        );
+        assert_eq!(
+            diagnostics(source),
+            ["W0001 1:12 `/*` within block comment"]
+        );
+    }
+
+    #[test]
+    fn reports_unterminated_comment() {
+        let source = "int x;\n  /* never\nclosed\n";
+        assert_eq!(
+            types(source),
+            [
+                TokenType::Integer,
+                TokenType::Identifier,
+                TokenType::Semicolon
+            ]
+        );
+        assert_eq!(
+            diagnostics(source),
+            ["E0004 2:3 unterminated block comment"]
+        );
+    }
+
+    #[test]
+    fn reports_each_unknown_character() {
+        use TokenType::*;
+        let source = "int @x = $1;\n`\nint é;";
+        assert_eq!(
+            types(source),
+            [
+                Integer, Error, Identifier, Assign, Error, Literal, Semicolon, Error, Integer,
+                Error, Semicolon
+            ]
+        );
+        assert_eq!(
+            diagnostics(source),
+            [
+                "E0005 1:5 unknown character '@'",
+                "E0005 1:10 unknown character '$'",
+                "E0005 2:1 unknown character '`'",
+                "E0005 3:5 unknown character 'é'",
+            ]
+        );
+    }
+
+    fn literal(text: &str) -> (u64, IntKind) {
+        let literal = int_literal(text).unwrap();
+        (literal.value, literal.kind)
+    }
+
+    #[test]
+    fn decodes_integer_constants() {
+        assert_eq!(literal("42"), (42, IntKind::Int));
+        assert_eq!(literal("0x1F"), (31, IntKind::Int));
+        assert_eq!(literal("0XfF"), (255, IntKind::Int));
+        assert_eq!(literal("0755"), (493, IntKind::Int));
+        assert_eq!(literal("0"), (0, IntKind::Int));
+        assert_eq!(literal("0b101"), (5, IntKind::Int));
+        assert_eq!(literal("10u"), (10, IntKind::UInt));
+        assert_eq!(literal("1L"), (1, IntKind::Long));
+        assert_eq!(literal("7lu"), (7, IntKind::ULong));
+        assert_eq!(literal("10ull"), (10, IntKind::ULongLong));
+        assert_eq!(literal("3LL"), (3, IntKind::LongLong));
+    }
+
+    #[test]
+    fn picks_the_first_type_that_fits() {
+        // Decimal constants without a suffix stay signed; octal and hex ones
+        // may become unsigned.
+        assert_eq!(literal("2147483647"), (2147483647, IntKind::Int));
+        assert_eq!(literal("2147483648"), (2147483648, IntKind::Long));
+        assert_eq!(literal("0x80000000"), (0x8000_0000, IntKind::UInt));
+        assert_eq!(literal("4294967296u"), (4294967296, IntKind::ULong));
+        assert_eq!(literal("0xffffffffffffffff"), (u64::MAX, IntKind::ULong));
+        assert_eq!(literal("2147483648LL"), (2147483648, IntKind::LongLong));
+
+        let big = int_literal("18446744073709551615").unwrap();
+        assert_eq!(
+            (big.kind, big.implicitly_unsigned),
+            (IntKind::ULongLong, true)
+        );
+    }
+
+    #[test]
+    fn reports_bad_integer_constants() {
+        let source = "09 0b2 0x 1lL 12abc 18446744073709551616 18446744073709551615";
+        let mut expected = vec![TokenType::Error; 6];
+        expected.push(TokenType::Literal);
+        assert_eq!(types(source), expected);
+        assert_eq!(
+            diagnostics(source),
+            [
+                "E0006 1:1 invalid digit '9' in octal constant",
+                "E0006 1:4 invalid digit '2' in binary constant",
+                "E0006 1:8 integer constant `0x` has no digits",
+                "E0006 1:11 invalid suffix `lL` on integer constant",
+                "E0006 1:15 invalid suffix `abc` on integer constant",
+                "E0007 1:21 integer constant `18446744073709551616` is too large for any integer type",
+                "W0002 1:42 integer constant is too large for a signed type, treating it as `unsigned long long`",
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_escape_sequences() {
+        let (bytes, errors) = unescape(r#"a\n\t\x41\0\101\177\\\'\"\?"#);
+        assert_eq!(bytes, b"a\n\tA\0A\x7f\\'\"?");
+        assert!(errors.is_empty());
+
+        // Octal escapes take at most three digits; hex escapes take them all.
+        assert_eq!(unescape(r"\1234").0, b"S4");
+        assert_eq!(
+            unescape(r"\x4142").1[0].message,
+            "hex escape sequence out of range"
+        );
+    }
+
+    #[test]
+    fn values_of_character_constants() {
+        assert_eq!(char_value(b"a"), 97);
+        assert_eq!(char_value(b"\xff"), -1);
+        assert_eq!(char_value(b"ab"), 0x6162);
+    }
+
+    #[test]
+    fn lexes_character_and_string_literals() {
+        use TokenType::*;
+        let source = r#"'a' '\'' "say \"hi\"\n" "a" "b" '"' "'""#;
+        assert_eq!(
+            tokens(source),
+            [
+                (CharLiteral, String::from("'a'")),
+                (CharLiteral, String::from(r"'\''")),
+                (StringLiteral, String::from(r#""say \"hi\"\n""#)),
+                (StringLiteral, String::from(r#""a""#)),
+                (StringLiteral, String::from(r#""b""#)),
+                (CharLiteral, String::from("'\"'")),
+                (StringLiteral, String::from("\"'\"")),
+            ]
+        );
+        assert_eq!(diagnostics(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_bad_character_and_string_literals() {
+        let source = "\
+'' 'abcde' 'ab' \"\\q\" \"\\x\" '\\400'
+\"open
+'x
+";
+        assert_eq!(
+            diagnostics(source),
+            [
+                "E0006 1:1 empty character constant",
+                "E0006 1:4 character constant too long for its type",
+                "W0004 1:12 multi-character character constant",
+                "W0003 1:18 unknown escape sequence `\\q`",
+                "E0009 1:23 `\\x` used with no following hex digits",
+                "E0009 1:28 octal escape sequence out of range",
+                "E0008 2:1 unterminated string literal",
+                "E0008 3:1 unterminated character constant",
+            ]
+        );
+    }
 }
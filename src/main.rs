@@ -5,24 +5,30 @@ Copyright Kevin Qiu 2023
 */
 
 extern crate getopts;
+mod constant;
+mod diagnostics;
 mod generator;
 mod lexer;
 mod parser;
+mod span;
+mod types;
 
+use diagnostics::{Diagnostic, Format};
+use generator::Standard;
 use getopts::Options;
+use span::SourceMap;
 use std::env;
 use std::fs;
-use std::path::Path;
 use std::process::Command;
 
-static VERSION: &'static str = "0.1.0";
+static VERSION: &str = "0.1.0";
 
 fn print_usage(prog: &str, opts: Options) {
     println!("crust {}", VERSION);
-    println!("");
+    println!();
     println!("Usage:");
     println!("{} [OPTION]... [FILE]...", prog);
-    println!("");
+    println!();
     println!("{}", opts.usage("C compiler in Rust"));
     println!("Source code: <https://github.com/kzqiu/crust>");
 }
@@ -30,7 +36,7 @@ fn print_usage(prog: &str, opts: Options) {
 fn main() {
     let args: Vec<String> = env::args().collect();
     let mut opts = Options::new();
-    let mut output_path = String::from("crust_out");
+    let output_path = String::from("crust_out");
 
     opts.optflag("h", "help", "display help and exit");
     opts.optflag("V", "version", "display current version");
@@ -41,6 +47,14 @@ fn main() {
         output_path.as_str(),
     );
 
+    opts.optopt(
+        "",
+        "diagnostics-format",
+        "how to print errors and warnings",
+        "human|json",
+    );
+    opts.optopt("", "std", "language standard to follow", "c89|c99|c11|c17");
+
     let matches = opts.parse(&args[1..]).unwrap();
 
     // Handling different flags
@@ -63,6 +77,26 @@ fn main() {
     //     }
     // }
 
+    let format = match matches.opt_str("diagnostics-format").as_deref() {
+        None | Some("human") => Format::Human,
+        Some("json") => Format::Json,
+        Some(other) => {
+            println!("Unknown diagnostics format \"{}\".", other);
+            return;
+        }
+    };
+
+    let standard = match matches.opt_str("std").as_deref() {
+        Some("c89") | Some("c90") => Standard::C89,
+        Some("c99") => Standard::C99,
+        Some("c11") => Standard::C11,
+        None | Some("c17") | Some("c18") => Standard::C17,
+        Some(other) => {
+            println!("Unknown language standard \"{}\".", other);
+            return;
+        }
+    };
+
     let input = if matches.free.len() == 1 {
         matches.free[0].clone()
     } else {
@@ -73,24 +107,44 @@ fn main() {
     // let stem = Path::new(&input).file_stem();
     // output_path = stem.unwrap().to_str().unwrap().to_string();
 
-    if let Ok(file) = fs::read_to_string(input) {
-        let tokens: Vec<lexer::Token> = lexer::lex(&file);
+    if let Ok(file) = fs::read_to_string(&input) {
+        let mut sources = SourceMap::default();
+        let file_id = sources.add(&input, file);
+
+        let (tokens, lex_diagnostics) = lexer::lex(&sources.get(file_id).text, file_id);
+        diagnostics::emit(&lex_diagnostics, &sources, format);
+        if lex_diagnostics.iter().any(Diagnostic::is_error) {
+            std::process::exit(1);
+        }
         // dbg!(tokens);
-        let program: parser::Program = parser::parse(&tokens);
-        let asm = generator::generate(program);
+        let program: parser::Program = match parser::parse(&tokens) {
+            Ok(program) => program,
+            Err(errors) => {
+                let errors: Vec<Diagnostic> = errors.into_iter().map(Diagnostic::from).collect();
+                diagnostics::emit(&errors, &sources, format);
+                std::process::exit(1);
+            }
+        };
+        let (asm, gen_diagnostics) = generator::generate(program, standard);
+        diagnostics::emit(&gen_diagnostics, &sources, format);
+        if gen_diagnostics.iter().any(Diagnostic::is_error) {
+            std::process::exit(1);
+        }
 
         generator::write_asm(output_path.as_str(), asm.as_str());
 
-        let output = Command::new("zsh")
-            .arg("-c")
-            .arg(format!("gcc {}.s -o {}", output_path, output_path))
+        let output = Command::new("gcc")
+            .arg(format!("{}.s", output_path))
+            .arg("-o")
+            .arg(&output_path)
             .output()
-            .unwrap();
+            .expect("failed to run gcc");
 
-        let output = String::from_utf8(output.stdout).unwrap();
-        println!("{}", output);
+        if !output.status.success() {
+            eprint!("{}", String::from_utf8_lossy(&output.stderr));
+            std::process::exit(1);
+        }
     } else {
         println!("Please input a valid path.");
-        return;
     }
 }
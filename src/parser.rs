@@ -1,101 +1,273 @@
-use crate::lexer::{Token, TokenType};
-use std::iter::Peekable;
+use crate::diagnostics::{self, Diagnostic};
+use crate::lexer::{self, IntKind, IntLiteral, Token, TokenType};
+use crate::span::Span;
+use crate::types::Type;
+use std::fmt;
+use std::iter::{once, Peekable};
+use std::rc::Rc;
 use std::slice::Iter;
 
 pub struct Program {
-    pub functions: Vec<Function>,
+    pub items: Vec<TopLevel>,
+}
+
+// A function or a file-scope variable declaration.
+pub enum TopLevel {
+    Function(Function),
+    // A declaration of one or more variables, such as `int a, *p;`.
+    Declaration(Vec<Declaration>),
+    // A declaration of only a tag, such as `struct point { int x, y; };`.
+    Tag(Type, Span),
 }
 
 pub struct Function {
     pub name: String,
-    pub blocks: Vec<BlockItem>,
-    // pub params: Vec<(String, TokenType)>,
-    // pub return_type: TokenType,
+    // `static` or `extern`, if given.
+    pub storage: Option<TokenType>,
+    pub return_type: Type,
+    // None for `()`, which says nothing about the parameters; `(void)` is an
+    // empty list.
+    pub params: Option<Vec<Param>>,
+    // None for a prototype such as `int f(int a);`.
+    pub blocks: Option<Vec<BlockItem>>,
+    pub span: Span,
+}
+
+pub struct Param {
+    // Optional in a declaration without a body.
+    pub name: Option<String>,
+    pub ty: Type,
+    // Array dimensions, as in a declaration.
+    pub dims: Vec<Option<Expression>>,
+    pub span: Span,
+}
+
+// A `struct`, `union` or `enum` specifier, such as `struct point`,
+// `union { int i; char c; }` or `enum color { RED, GREEN }`.
+pub struct TagSpecifier {
+    // `Struct`, `Union` or `Enum`.
+    pub kind: TokenType,
+    pub tag: Option<String>,
+    // The member declarations or enumerators, if this is a definition.
+    pub body: Option<TagBody>,
+    pub span: Span,
+}
+
+pub enum TagBody {
+    Fields(Vec<Field>),
+    Enumerators(Vec<Enumerator>),
+}
+
+// Each specifier declares or refers to a tag separately, so they compare by
+// identity.
+impl PartialEq for TagSpecifier {
+    fn eq(&self, other: &TagSpecifier) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for TagSpecifier {}
+
+impl fmt::Display for TagSpecifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keyword = match self.kind {
+            TokenType::Union => "union",
+            TokenType::Enum => "enum",
+            _ => "struct",
+        };
+        match &self.tag {
+            Some(tag) => write!(f, "{} {}", keyword, tag),
+            None => write!(f, "{} <anonymous>", keyword),
+        }
+    }
+}
+
+// Members are left out, as they may refer back to the tag.
+impl fmt::Debug for TagSpecifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+// `RED` or `RED = 1` in an enum definition.
+pub struct Enumerator {
+    pub name: String,
+    // A constant expression; without one, the value is one more than the
+    // previous enumerator's, or 0 for the first.
+    pub value: Option<Expression>,
+    pub span: Span,
+}
+
+// A member of a struct or union definition.
+pub struct Field {
+    // None for an anonymous struct or union member.
+    pub name: Option<String>,
+    pub ty: Type,
+    pub dims: Vec<Option<Expression>>,
+    pub span: Span,
 }
 
 // Highest Precedence for Binary Operators
 pub enum Factor {
-    Expr(Box<Expression>),
-    UnaryOp(TokenType, Box<Factor>),
-    Number(i32),
-    Identifier(String),
+    Expr(Box<Expression>, Span),
+    // Includes `&` (BitAnd) and `*` (Multiplication).
+    UnaryOp(TokenType, Box<Factor>, Span),
+    // `(type) factor`
+    Cast(Type, Box<Factor>, Span),
+    // `x++` or `x--`; prefix forms are unary operators.
+    Postfix(TokenType, Box<Factor>, Span),
+    // `a[i]`
+    Index(Box<Factor>, Box<Expression>, Span),
+    // `s.member` (Dot) or `p->member` (Arrow)
+    Member(TokenType, Box<Factor>, String, Span),
+    // `sizeof x` and `sizeof(type)`
+    SizeOf(Box<Factor>, Span),
+    SizeOfType(Type, Span),
+    Number(IntLiteral, Span),
+    String(Vec<u8>, Span),
+    Identifier(String, Span),
+    // A call to the named function with its arguments.
+    Call(String, Vec<Expression>, Span),
 }
 
 pub struct Term {
     pub factor: Factor,
     pub additional: Vec<(TokenType, Factor)>,
+    pub span: Span,
 }
 
 pub struct AdditiveExpr {
     pub term: Term,
     pub additional: Vec<(TokenType, Term)>,
+    pub span: Span,
 }
 
 pub struct ShiftExpr {
     pub add_expr: AdditiveExpr,
     pub additional: Vec<(TokenType, AdditiveExpr)>,
+    pub span: Span,
 }
 
 pub struct RelationalExpr {
     pub shift_expr: ShiftExpr,
     pub additional: Vec<(TokenType, ShiftExpr)>,
+    pub span: Span,
 }
 
 pub struct EqualityExpr {
     pub rel_expr: RelationalExpr,
     pub additional: Vec<(TokenType, RelationalExpr)>,
+    pub span: Span,
 }
 
 pub struct BitAndExpr {
     pub eq_expr: EqualityExpr,
     pub additional: Vec<EqualityExpr>,
+    pub span: Span,
 }
 
 pub struct BitXOrExpr {
     pub bit_and_expr: BitAndExpr,
     pub additional: Vec<BitAndExpr>,
+    pub span: Span,
 }
 
 pub struct BitOrExpr {
     pub bit_xor_expr: BitXOrExpr,
     pub additional: Vec<BitXOrExpr>,
+    pub span: Span,
 }
 
 pub struct LogicalAndExpr {
     pub bit_or_expr: BitOrExpr,
     pub additional: Vec<BitOrExpr>,
+    pub span: Span,
 }
 
 // Lowest Precedence for Binary Operators
 pub struct LogicalOrExpr {
     pub log_and_expr: LogicalAndExpr,
     pub additional: Vec<LogicalAndExpr>,
+    pub span: Span,
 }
 
 pub struct ConditionalExpr {
     pub log_or_expr: LogicalOrExpr,
     pub additional: Option<(Box<Expression>, Box<ConditionalExpr>)>,
+    pub span: Span,
 }
 
 pub enum Expression {
-    Assign(String, Box<Expression>),
-    Conditional(ConditionalExpr),
+    // `=` or one of the compound assignment operators, the target, and the
+    // value. The target is checked to be an lvalue during code generation.
+    Assign(TokenType, Box<Factor>, Box<Expression>, Span),
+    Conditional(Box<ConditionalExpr>),
 }
 
 pub enum Statement {
-    Return(Expression),
+    Return(Option<Expression>, Span),
+    Expr(Expression, Span),
+    If(Expression, Box<Statement>, Option<Box<Statement>>, Span),
+    Block(Vec<BlockItem>, Span),
+    While(Expression, Box<Statement>, Span),
+    DoWhile(Box<Statement>, Expression, Span),
+    // for (init; condition; post) body
+    For(
+        Option<ForInit>,
+        Option<Expression>,
+        Option<Expression>,
+        Box<Statement>,
+        Span,
+    ),
+    Break(Span),
+    Continue(Span),
+    Switch(Expression, Box<Statement>, Span),
+    // `case <expr>:` and `default:` label the statement that follows them.
+    Case(Expression, Box<Statement>, Span),
+    Default(Box<Statement>, Span),
+    Goto(String, Span),
+    // `<label>: <statement>`
+    Labeled(String, Box<Statement>, Span),
+    // A lone `;`.
+    Null(Span),
+}
+
+pub enum ForInit {
+    Declaration(Vec<Declaration>),
     Expr(Expression),
-    If(Expression, Box<Statement>, Option<Box<Statement>>),
 }
 
 pub struct Declaration {
     pub identifier: String,
-    pub expr: Option<Expression>,
+    // `static` or `extern`, if given.
+    pub storage: Option<TokenType>,
+    pub ty: Type,
+    // Array dimensions, outermost first, applied to `ty`. Sizes are constant
+    // expressions, evaluated during code generation; only the first may be
+    // left out.
+    pub dims: Vec<Option<Expression>>,
+    pub init: Option<Initializer>,
+    pub span: Span,
+}
+
+// `= <expr>`, or a braced list such as `= {1, {2, 3}}`, whose items may be
+// designated as in `= {.x = 1, [2] = 3}`.
+pub enum Initializer {
+    Expr(Expression),
+    List(Vec<(Vec<Designator>, Initializer)>, Span),
+}
+
+pub enum Designator {
+    // `.member`
+    Member(String, Span),
+    // `[index]`, a constant expression.
+    Index(Expression, Span),
 }
 
 pub enum BlockItem {
     Statement(Statement),
-    Declaration(Declaration),
+    Declaration(Vec<Declaration>),
+    Tag(Type, Span),
 }
 
 pub enum NodeType {
@@ -105,497 +277,1653 @@ pub enum NodeType {
     Statement,
 }
 
-fn parse_factor(tokens: &mut Peekable<Iter<'_, Token>>) -> Factor {
-    let next = tokens.next().unwrap();
+#[derive(Debug)]
+pub struct ParseError {
+    pub expected: String,
+    pub found: String,
+    pub span: Span,
+    // Text that fixes the error when inserted before the found token.
+    pub insert: Option<&'static str>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(err: ParseError) -> Diagnostic {
+        let diagnostic = Diagnostic::error(format!("expected {}", err.expected), err.span)
+            .with_code(diagnostics::SYNTAX_ERROR)
+            .with_label(format!("found {}", err.found));
+
+        match err.insert {
+            Some(text) => {
+                let at = Span {
+                    end: err.span.start,
+                    ..err.span
+                };
+                diagnostic.with_suggestion(at, text, "try inserting")
+            }
+            None => diagnostic,
+        }
+    }
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+impl Factor {
+    pub fn span(&self) -> Span {
+        match self {
+            Factor::Expr(_, span)
+            | Factor::UnaryOp(_, _, span)
+            | Factor::Cast(_, _, span)
+            | Factor::Postfix(_, _, span)
+            | Factor::Index(_, _, span)
+            | Factor::Member(_, _, _, span)
+            | Factor::SizeOf(_, span)
+            | Factor::SizeOfType(_, span)
+            | Factor::Number(_, span)
+            | Factor::String(_, span)
+            | Factor::Identifier(_, span)
+            | Factor::Call(_, _, span) => *span,
+        }
+    }
+}
+
+impl ConditionalExpr {
+    // The lone factor this expression consists of, if it has no operators.
+    pub fn as_factor(&self) -> Option<&Factor> {
+        let term = &self
+            .log_or_expr
+            .log_and_expr
+            .bit_or_expr
+            .bit_xor_expr
+            .bit_and_expr
+            .eq_expr
+            .rel_expr
+            .shift_expr
+            .add_expr
+            .term;
+        if self.is_single_factor() {
+            Some(&term.factor)
+        } else {
+            None
+        }
+    }
+
+    // Like `as_factor`, but takes ownership; gives the expression back when it
+    // has operators.
+    pub fn into_factor(self: Box<Self>) -> Result<Factor, Box<ConditionalExpr>> {
+        if !self.is_single_factor() {
+            return Err(self);
+        }
+        Ok(self
+            .log_or_expr
+            .log_and_expr
+            .bit_or_expr
+            .bit_xor_expr
+            .bit_and_expr
+            .eq_expr
+            .rel_expr
+            .shift_expr
+            .add_expr
+            .term
+            .factor)
+    }
+
+    // The additive expression this consists of, if it has no operators of
+    // lower precedence.
+    pub fn as_additive(&self) -> Option<&AdditiveExpr> {
+        let log_or = &self.log_or_expr;
+        let log_and = &log_or.log_and_expr;
+        let bit_or = &log_and.bit_or_expr;
+        let bit_xor = &bit_or.bit_xor_expr;
+        let bit_and = &bit_xor.bit_and_expr;
+        let eq = &bit_and.eq_expr;
+        let rel = &eq.rel_expr;
+        let shift = &rel.shift_expr;
+        let single = self.additional.is_none()
+            && log_or.additional.is_empty()
+            && log_and.additional.is_empty()
+            && bit_or.additional.is_empty()
+            && bit_xor.additional.is_empty()
+            && bit_and.additional.is_empty()
+            && eq.additional.is_empty()
+            && rel.additional.is_empty()
+            && shift.additional.is_empty();
+        single.then_some(&shift.add_expr)
+    }
+
+    // The operands of every binary operator in this expression, outermost
+    // first, leaving out the branches of `?:`.
+    pub fn factors(&self) -> Vec<&Factor> {
+        let mut factors = Vec::new();
+        let log_or = &self.log_or_expr;
+        for log_and in once(&log_or.log_and_expr).chain(&log_or.additional) {
+            for bit_or in once(&log_and.bit_or_expr).chain(&log_and.additional) {
+                for bit_xor in once(&bit_or.bit_xor_expr).chain(&bit_or.additional) {
+                    for bit_and in once(&bit_xor.bit_and_expr).chain(&bit_xor.additional) {
+                        for eq in once(&bit_and.eq_expr).chain(&bit_and.additional) {
+                            let rels = eq.additional.iter().map(|(_, rel)| rel);
+                            for rel in once(&eq.rel_expr).chain(rels) {
+                                let shifts = rel.additional.iter().map(|(_, shift)| shift);
+                                for shift in once(&rel.shift_expr).chain(shifts) {
+                                    let adds = shift.additional.iter().map(|(_, add)| add);
+                                    for add in once(&shift.add_expr).chain(adds) {
+                                        let terms = add.additional.iter().map(|(_, term)| term);
+                                        for term in once(&add.term).chain(terms) {
+                                            let rest = term.additional.iter().map(|(_, f)| f);
+                                            factors.extend(once(&term.factor).chain(rest));
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        factors
+    }
+
+    fn is_single_factor(&self) -> bool {
+        self.as_additive()
+            .is_some_and(|add| add.additional.is_empty() && add.term.additional.is_empty())
+    }
+}
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Assign(_, _, _, span) => *span,
+            Expression::Conditional(cond) => cond.span,
+        }
+    }
+
+    // The lone factor this expression consists of, looking through
+    // parentheses.
+    pub fn as_factor(&self) -> Option<&Factor> {
+        let Expression::Conditional(cond) = self else {
+            return None;
+        };
+        match cond.as_factor()? {
+            Factor::Expr(inner, _) => inner.as_factor(),
+            factor => Some(factor),
+        }
+    }
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Return(_, span)
+            | Statement::Expr(_, span)
+            | Statement::If(_, _, _, span)
+            | Statement::Block(_, span)
+            | Statement::While(_, _, span)
+            | Statement::DoWhile(_, _, span)
+            | Statement::For(_, _, _, _, span)
+            | Statement::Break(span)
+            | Statement::Null(span)
+            | Statement::Continue(span)
+            | Statement::Switch(_, _, span)
+            | Statement::Case(_, _, span)
+            | Statement::Default(_, span)
+            | Statement::Goto(_, span)
+            | Statement::Labeled(_, _, span) => *span,
+        }
+    }
+}
+
+impl Initializer {
+    pub fn span(&self) -> Span {
+        match self {
+            Initializer::Expr(expr) => expr.span(),
+            Initializer::List(_, span) => *span,
+        }
+    }
+}
+
+impl Designator {
+    pub fn span(&self) -> Span {
+        match self {
+            Designator::Member(_, span) | Designator::Index(_, span) => *span,
+        }
+    }
+}
+
+impl BlockItem {
+    pub fn span(&self) -> Span {
+        match self {
+            BlockItem::Statement(statement) => statement.span(),
+            BlockItem::Declaration(declarations) => {
+                let first = declarations.first().unwrap().span;
+                first.to(declarations.last().unwrap().span)
+            }
+            BlockItem::Tag(_, span) => *span,
+        }
+    }
+}
+
+// Describes a token the way it should appear in an error message.
+fn describe(tk: &Token) -> String {
+    match tk.token_type {
+        TokenType::Eof => String::from("end of file"),
+        TokenType::Identifier => format!("identifier `{}`", tk.text),
+        TokenType::Literal | TokenType::CharLiteral | TokenType::StringLiteral => {
+            format!("literal `{}`", tk.text)
+        }
+        TokenType::Integer
+        | TokenType::Return
+        | TokenType::If
+        | TokenType::Else
+        | TokenType::While
+        | TokenType::Do
+        | TokenType::For
+        | TokenType::Break
+        | TokenType::Continue
+        | TokenType::Switch
+        | TokenType::Case
+        | TokenType::Default
+        | TokenType::Goto
+        | TokenType::Void
+        | TokenType::Char
+        | TokenType::Short
+        | TokenType::Long
+        | TokenType::Signed
+        | TokenType::Unsigned
+        | TokenType::Static
+        | TokenType::Extern
+        | TokenType::Sizeof
+        | TokenType::Struct
+        | TokenType::Union
+        | TokenType::Enum => format!("keyword `{}`", tk.text),
+        _ => format!("`{}`", tk.text),
+    }
+}
+
+fn unexpected(expected: &str, found: &Token) -> ParseError {
+    ParseError {
+        expected: expected.to_string(),
+        found: describe(found),
+        span: found.span,
+        insert: None,
+    }
+}
+
+// The end of file token is never consumed, so the stream can't run dry.
+fn peek_token<'a>(tokens: &mut Peekable<Iter<'a, Token>>) -> &'a Token {
+    tokens.peek().expect("token stream must end with Eof")
+}
+
+fn peek_type(tokens: &mut Peekable<Iter<'_, Token>>) -> TokenType {
+    peek_token(tokens).token_type
+}
+
+fn next_token<'a>(tokens: &mut Peekable<Iter<'a, Token>>) -> &'a Token {
+    let tk = peek_token(tokens);
+    if tk.token_type != TokenType::Eof {
+        tokens.next();
+    }
+    tk
+}
+
+fn expect<'a>(
+    tokens: &mut Peekable<Iter<'a, Token>>,
+    token_type: TokenType,
+    expected: &str,
+) -> ParseResult<&'a Token> {
+    let tk = peek_token(tokens);
+    if tk.token_type == token_type {
+        Ok(next_token(tokens))
+    } else {
+        let mut err = unexpected(expected, tk);
+        err.insert = match token_type {
+            TokenType::Semicolon => Some(";"),
+            TokenType::RParen => Some(")"),
+            TokenType::RBrace => Some("}"),
+            TokenType::Colon => Some(":"),
+            _ => None,
+        };
+        Err(err)
+    }
+}
+
+// Panic-mode recovery: skip ahead to the end of the current statement, at
+// the depth of braces the error was found at. A `;` or the `}` of a block
+// skipped into is consumed; the keyword of the next statement, or the `}` of
+// the enclosing block, is left to be parsed.
+fn synchronize(tokens: &mut Peekable<Iter<'_, Token>>) {
+    let mut depth = 0;
+    loop {
+        match peek_type(tokens) {
+            TokenType::Eof => return,
+            TokenType::Semicolon if depth == 0 => {
+                tokens.next();
+                return;
+            }
+            TokenType::RBrace if depth == 0 => return,
+            TokenType::RBrace => {
+                tokens.next();
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            }
+            TokenType::LBrace => {
+                tokens.next();
+                depth += 1;
+            }
+            TokenType::If
+            | TokenType::While
+            | TokenType::Do
+            | TokenType::For
+            | TokenType::Switch
+            | TokenType::Return
+            | TokenType::Break
+            | TokenType::Continue
+            | TokenType::Goto
+                if depth == 0 =>
+            {
+                return
+            }
+            _ => {
+                tokens.next();
+            }
+        }
+    }
+}
+
+// Recovers from an error outside a function body by skipping past the end of
+// the current top-level definition. `depth` is how many of its braces the
+// error was inside, as in a struct body or an initializer list.
+fn skip_definition(tokens: &mut Peekable<Iter<'_, Token>>, mut depth: usize) {
+    loop {
+        match peek_type(tokens) {
+            TokenType::Eof => return,
+            TokenType::LBrace => depth += 1,
+            TokenType::RBrace if depth <= 1 => {
+                tokens.next();
+                return;
+            }
+            TokenType::RBrace => depth -= 1,
+            TokenType::Semicolon if depth == 0 => {
+                tokens.next();
+                return;
+            }
+            _ => {}
+        }
+        tokens.next();
+    }
+}
+
+fn parse_factor(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Factor> {
+    let next = peek_token(tokens);
+    match next.token_type {
+        TokenType::Minus
+        | TokenType::BitComplement
+        | TokenType::LogicalNeg
+        | TokenType::Increment
+        | TokenType::Decrement
+        | TokenType::BitAnd
+        | TokenType::Multiplication => {
+            tokens.next();
+            let op = next.token_type;
+            let factor = parse_factor(tokens)?;
+            let span = next.span.to(factor.span());
+            Ok(Factor::UnaryOp(op, Box::new(factor), span))
+        }
+        TokenType::Sizeof => {
+            tokens.next();
+            let is_type = peek_type(tokens) == TokenType::LParen
+                && tokens
+                    .clone()
+                    .nth(1)
+                    .is_some_and(|tk| is_type_specifier(tk.token_type));
+            if is_type {
+                tokens.next();
+                let (ty, _) = parse_type(tokens, "type name")?;
+                let close = expect(tokens, TokenType::RParen, "`)` after type name")?;
+                return Ok(Factor::SizeOfType(ty, next.span.to(close.span)));
+            }
+            let factor = parse_factor(tokens)?;
+            let span = next.span.to(factor.span());
+            Ok(Factor::SizeOf(Box::new(factor), span))
+        }
+        // A parenthesized type name starts a cast.
+        TokenType::LParen
+            if tokens
+                .clone()
+                .nth(1)
+                .is_some_and(|tk| is_type_specifier(tk.token_type)) =>
+        {
+            tokens.next();
+            let (ty, _) = parse_type(tokens, "type name")?;
+            expect(tokens, TokenType::RParen, "`)` after type name")?;
+            let factor = parse_factor(tokens)?;
+            let span = next.span.to(factor.span());
+            Ok(Factor::Cast(ty, Box::new(factor), span))
+        }
+        _ => parse_postfix(tokens),
+    }
+}
+
+// Postfix operators bind tighter than prefix ones, so `-x++` is `-(x++)`.
+fn parse_postfix(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Factor> {
+    let mut factor = parse_primary(tokens)?;
+
+    loop {
+        match peek_type(tokens) {
+            op @ (TokenType::Increment | TokenType::Decrement) => {
+                let tk = next_token(tokens);
+                let span = factor.span().to(tk.span);
+                factor = Factor::Postfix(op, Box::new(factor), span);
+            }
+            TokenType::LBracket => {
+                tokens.next();
+                let index = parse_expr(tokens)?;
+                let close = expect(tokens, TokenType::RBracket, "`]` after subscript")?;
+                let span = factor.span().to(close.span);
+                factor = Factor::Index(Box::new(factor), Box::new(index), span);
+            }
+            op @ (TokenType::Dot | TokenType::Arrow) => {
+                tokens.next();
+                let name = expect(tokens, TokenType::Identifier, "member name")?;
+                let span = factor.span().to(name.span);
+                factor = Factor::Member(op, Box::new(factor), name.text.to_string(), span);
+            }
+            _ => break,
+        }
+    }
+
+    Ok(factor)
+}
+
+fn parse_primary(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Factor> {
+    let next = peek_token(tokens);
     match next.token_type {
         TokenType::LParen => {
-            let expr = parse_expr(tokens);
-            match tokens.next().unwrap().token_type {
-                TokenType::RParen => {}
-                _ => panic!(),
+            tokens.next();
+            let expr = parse_expr(tokens)?;
+            let close = expect(tokens, TokenType::RParen, "`)`")?;
+            Ok(Factor::Expr(Box::new(expr), next.span.to(close.span)))
+        }
+        TokenType::Literal => {
+            tokens.next();
+            match lexer::int_literal(&next.text) {
+                Ok(literal) => Ok(Factor::Number(literal, next.span)),
+                Err(_) => Err(unexpected("a valid integer constant", next)),
             }
-            Factor::Expr(Box::new(expr))
         }
-        TokenType::Minus | TokenType::BitComplement | TokenType::LogicalNeg => {
-            let op = next.token_type;
-            let factor = parse_factor(tokens);
-            Factor::UnaryOp(op, Box::new(factor))
+        TokenType::CharLiteral => {
+            tokens.next();
+            let (bytes, _) = lexer::unescape(&next.text[1..next.text.len() - 1]);
+            let literal = IntLiteral {
+                value: lexer::char_value(&bytes) as u32 as u64,
+                kind: IntKind::Int,
+                implicitly_unsigned: false,
+            };
+            Ok(Factor::Number(literal, next.span))
         }
-        TokenType::Literal => Factor::Number(next.text.parse::<i32>().unwrap()),
-        TokenType::Identifier => Factor::Identifier(next.text.to_string()),
-        _ => {
-            dbg!(next);
-            panic!();
+        TokenType::StringLiteral => {
+            // Adjacent string literals are concatenated.
+            let mut bytes = Vec::new();
+            let mut span = next.span;
+            while peek_type(tokens) == TokenType::StringLiteral {
+                let tk = next_token(tokens);
+                bytes.extend(lexer::unescape(&tk.text[1..tk.text.len() - 1]).0);
+                span = span.to(tk.span);
+            }
+            Ok(Factor::String(bytes, span))
         }
+        TokenType::Identifier => {
+            tokens.next();
+            if peek_type(tokens) != TokenType::LParen {
+                return Ok(Factor::Identifier(next.text.to_string(), next.span));
+            }
+
+            tokens.next();
+            let mut args = Vec::new();
+            if peek_type(tokens) != TokenType::RParen {
+                loop {
+                    args.push(parse_expr(tokens)?);
+                    if peek_type(tokens) != TokenType::Comma {
+                        break;
+                    }
+                    tokens.next();
+                }
+            }
+            let close = expect(tokens, TokenType::RParen, "`)` after arguments")?;
+            Ok(Factor::Call(
+                next.text.to_string(),
+                args,
+                next.span.to(close.span),
+            ))
+        }
+        _ => Err(unexpected("expression", next)),
     }
 }
 
-fn parse_term(tokens: &mut Peekable<Iter<'_, Token>>) -> Term {
-    let factor = parse_factor(tokens);
+fn parse_term(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Term> {
+    let factor = parse_factor(tokens)?;
 
     let mut term = Term {
+        span: factor.span(),
         factor,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match next.token_type {
-                TokenType::Multiplication | TokenType::Division => {
-                    let op = tokens.next().unwrap().token_type;
-                    let next_factor = parse_factor(tokens);
-                    term.additional.push((op, next_factor));
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while let op @ (TokenType::Multiplication | TokenType::Division | TokenType::Modulo) =
+        peek_type(tokens)
+    {
+        tokens.next();
+        let next_factor = parse_factor(tokens)?;
+        term.span = term.span.to(next_factor.span());
+        term.additional.push((op, next_factor));
     }
 
-    term
+    Ok(term)
 }
 
-fn parse_add_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> AdditiveExpr {
-    let term = parse_term(tokens);
+fn parse_add_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<AdditiveExpr> {
+    let term = parse_term(tokens)?;
 
     let mut expr = AdditiveExpr {
+        span: term.span,
         term,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match &next.token_type {
-                TokenType::Addition | TokenType::Minus => {
-                    let op = tokens.next().unwrap().token_type;
-                    let next_expr = parse_term(tokens);
-                    expr.additional.push((op, next_expr));
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while let op @ (TokenType::Addition | TokenType::Minus) = peek_type(tokens) {
+        tokens.next();
+        let next_expr = parse_term(tokens)?;
+        expr.span = expr.span.to(next_expr.span);
+        expr.additional.push((op, next_expr));
     }
 
-    expr
+    Ok(expr)
 }
 
-fn parse_shift_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ShiftExpr {
-    let add_expr = parse_add_expr(tokens);
+fn parse_shift_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<ShiftExpr> {
+    let add_expr = parse_add_expr(tokens)?;
 
     let mut expr = ShiftExpr {
+        span: add_expr.span,
         add_expr,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match &next.token_type {
-                TokenType::LBitShift | TokenType::RBitShift => {
-                    let op = tokens.next().unwrap().token_type;
-                    let next_expr = parse_add_expr(tokens);
-                    expr.additional.push((op, next_expr));
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while let op @ (TokenType::LBitShift | TokenType::RBitShift) = peek_type(tokens) {
+        tokens.next();
+        let next_expr = parse_add_expr(tokens)?;
+        expr.span = expr.span.to(next_expr.span);
+        expr.additional.push((op, next_expr));
     }
 
-    expr
+    Ok(expr)
 }
 
-fn parse_rel_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> RelationalExpr {
-    let shift_expr = parse_shift_expr(tokens);
+fn parse_rel_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<RelationalExpr> {
+    let shift_expr = parse_shift_expr(tokens)?;
 
     let mut expr = RelationalExpr {
+        span: shift_expr.span,
         shift_expr,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match &next.token_type {
-                TokenType::LessThan
-                | TokenType::LessThanEqual
-                | TokenType::GreaterThan
-                | TokenType::GreaterThanEqual => {
-                    let op = tokens.next().unwrap().token_type;
-                    let next_expr = parse_shift_expr(tokens);
-                    expr.additional.push((op, next_expr));
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while let op @ (TokenType::LessThan
+    | TokenType::LessThanEqual
+    | TokenType::GreaterThan
+    | TokenType::GreaterThanEqual) = peek_type(tokens)
+    {
+        tokens.next();
+        let next_expr = parse_shift_expr(tokens)?;
+        expr.span = expr.span.to(next_expr.span);
+        expr.additional.push((op, next_expr));
     }
 
-    expr
+    Ok(expr)
 }
 
-fn parse_eq_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> EqualityExpr {
-    let rel_expr = parse_rel_expr(tokens);
+fn parse_eq_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<EqualityExpr> {
+    let rel_expr = parse_rel_expr(tokens)?;
 
     let mut expr = EqualityExpr {
+        span: rel_expr.span,
         rel_expr,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match &next.token_type {
-                TokenType::Equal | TokenType::NotEqual => {
-                    let op = tokens.next().unwrap().token_type;
-                    let next_expr = parse_rel_expr(tokens);
-                    expr.additional.push((op, next_expr));
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while let op @ (TokenType::Equal | TokenType::NotEqual) = peek_type(tokens) {
+        tokens.next();
+        let next_expr = parse_rel_expr(tokens)?;
+        expr.span = expr.span.to(next_expr.span);
+        expr.additional.push((op, next_expr));
     }
 
-    expr
+    Ok(expr)
 }
 
-fn parse_bit_and_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> BitAndExpr {
-    let eq_expr = parse_eq_expr(tokens);
+fn parse_bit_and_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<BitAndExpr> {
+    let eq_expr = parse_eq_expr(tokens)?;
 
     let mut expr = BitAndExpr {
+        span: eq_expr.span,
         eq_expr,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match &next.token_type {
-                TokenType::BitAnd => {
-                    let next_expr = parse_eq_expr(tokens);
-                    expr.additional.push(next_expr);
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while peek_type(tokens) == TokenType::BitAnd {
+        tokens.next();
+        let next_expr = parse_eq_expr(tokens)?;
+        expr.span = expr.span.to(next_expr.span);
+        expr.additional.push(next_expr);
     }
 
-    expr
+    Ok(expr)
 }
 
-fn parse_bit_xor_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> BitXOrExpr {
-    let bit_and_expr = parse_bit_and_expr(tokens);
+fn parse_bit_xor_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<BitXOrExpr> {
+    let bit_and_expr = parse_bit_and_expr(tokens)?;
 
     let mut expr = BitXOrExpr {
+        span: bit_and_expr.span,
         bit_and_expr,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match &next.token_type {
-                TokenType::BitXOr => {
-                    let next_expr = parse_bit_and_expr(tokens);
-                    expr.additional.push(next_expr);
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while peek_type(tokens) == TokenType::BitXOr {
+        tokens.next();
+        let next_expr = parse_bit_and_expr(tokens)?;
+        expr.span = expr.span.to(next_expr.span);
+        expr.additional.push(next_expr);
     }
 
-    expr
+    Ok(expr)
 }
 
-fn parse_bit_or_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> BitOrExpr {
-    let bit_xor_expr = parse_bit_xor_expr(tokens);
+fn parse_bit_or_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<BitOrExpr> {
+    let bit_xor_expr = parse_bit_xor_expr(tokens)?;
 
     let mut expr = BitOrExpr {
+        span: bit_xor_expr.span,
         bit_xor_expr,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match &next.token_type {
-                TokenType::BitOr => {
-                    let next_expr = parse_bit_xor_expr(tokens);
-                    expr.additional.push(next_expr);
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while peek_type(tokens) == TokenType::BitOr {
+        tokens.next();
+        let next_expr = parse_bit_xor_expr(tokens)?;
+        expr.span = expr.span.to(next_expr.span);
+        expr.additional.push(next_expr);
     }
 
-    expr
+    Ok(expr)
 }
 
-fn parse_log_and_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> LogicalAndExpr {
-    let bit_or_expr = parse_bit_or_expr(tokens);
+fn parse_log_and_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<LogicalAndExpr> {
+    let bit_or_expr = parse_bit_or_expr(tokens)?;
 
     let mut expr = LogicalAndExpr {
+        span: bit_or_expr.span,
         bit_or_expr,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match &next.token_type {
-                TokenType::And => {
-                    let next_expr = parse_bit_or_expr(tokens);
-                    expr.additional.push(next_expr);
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while peek_type(tokens) == TokenType::And {
+        tokens.next();
+        let next_expr = parse_bit_or_expr(tokens)?;
+        expr.span = expr.span.to(next_expr.span);
+        expr.additional.push(next_expr);
     }
 
-    expr
+    Ok(expr)
 }
 
-fn parse_log_or_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> LogicalOrExpr {
-    let log_and_expr = parse_log_and_expr(tokens);
+fn parse_log_or_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<LogicalOrExpr> {
+    let log_and_expr = parse_log_and_expr(tokens)?;
 
     let mut expr = LogicalOrExpr {
+        span: log_and_expr.span,
         log_and_expr,
         additional: Vec::new(),
     };
 
-    loop {
-        if let Some(next) = tokens.peek() {
-            match &next.token_type {
-                TokenType::Or => {
-                    let next_expr = parse_log_and_expr(tokens);
-                    expr.additional.push(next_expr);
-                }
-                _ => {
-                    break;
-                }
-            }
-        } else {
-            panic!();
-        }
+    while peek_type(tokens) == TokenType::Or {
+        tokens.next();
+        let next_expr = parse_log_and_expr(tokens)?;
+        expr.span = expr.span.to(next_expr.span);
+        expr.additional.push(next_expr);
     }
 
-    expr
+    Ok(expr)
 }
 
-fn parse_conditional_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ConditionalExpr {
-    ConditionalExpr {
-        log_or_expr: parse_log_or_expr(tokens),
-        additional: match tokens.peek().unwrap().token_type {
-            TokenType::QuestionMark => {
-                tokens.next();
-                let expr = parse_expr(tokens);
+fn parse_conditional_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<ConditionalExpr> {
+    let log_or_expr = parse_log_or_expr(tokens)?;
 
-                match tokens.next().unwrap().token_type {
-                    TokenType::Colon => {}
-                    _ => panic!(),
-                }
+    let mut expr = ConditionalExpr {
+        span: log_or_expr.span,
+        log_or_expr,
+        additional: None,
+    };
 
-                let cond_expr = parse_conditional_expr(tokens);
+    if peek_type(tokens) == TokenType::QuestionMark {
+        tokens.next();
+        let inner = parse_expr(tokens)?;
+        expect(tokens, TokenType::Colon, "`:` in conditional expression")?;
+        let cond_expr = parse_conditional_expr(tokens)?;
 
-                Some((Box::new(expr), Box::new(cond_expr)))
-            }
-            _ => None,
-        },
+        expr.span = expr.span.to(cond_expr.span);
+        expr.additional = Some((Box::new(inner), Box::new(cond_expr)));
     }
+
+    Ok(expr)
 }
 
-fn parse_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> Expression {
-    // let tk = tokens.next().unwrap();
-    let mut iter_cpy = tokens.clone();
-    let first_tk = iter_cpy.next().unwrap();
-    // dbg!(first_tk);
-    // dbg!(iter_cpy.peek().unwrap());
-    match (first_tk.token_type, iter_cpy.peek().unwrap().token_type) {
-        (TokenType::Identifier, TokenType::Assign) => {
-            tokens.next();
+fn parse_expr(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Expression> {
+    let cond = parse_conditional_expr(tokens)?;
+
+    match peek_type(tokens) {
+        op @ (TokenType::Assign
+        | TokenType::AssignPlus
+        | TokenType::AssignMinus
+        | TokenType::AssignMult
+        | TokenType::AssignDivide
+        | TokenType::AssignMod
+        | TokenType::AssignLBitShift
+        | TokenType::AssignRBitShift
+        | TokenType::AssignBitAnd
+        | TokenType::AssignBitOr
+        | TokenType::AssignBitXOr) => {
             tokens.next();
-            Expression::Assign(first_tk.text.to_string(), Box::new(parse_expr(tokens)))
+            // Assignment is right associative, so the value is a full expression.
+            let value = parse_expr(tokens)?;
+            let span = cond.span.to(value.span());
+            let target = match Box::new(cond).into_factor() {
+                Ok(factor) => factor,
+                Err(cond) => {
+                    let span = cond.span;
+                    Factor::Expr(Box::new(Expression::Conditional(cond)), span)
+                }
+            };
+            Ok(Expression::Assign(
+                op,
+                Box::new(target),
+                Box::new(value),
+                span,
+            ))
         }
-        _ => Expression::Conditional(parse_conditional_expr(tokens)),
+        _ => Ok(Expression::Conditional(Box::new(cond))),
+    }
+}
+
+// An expression that may be left out when `end` follows, as in `for (;;)`.
+fn parse_optional_expr(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    end: TokenType,
+) -> ParseResult<Option<Expression>> {
+    if peek_type(tokens) == end {
+        Ok(None)
+    } else {
+        parse_expr(tokens).map(Some)
     }
 }
 
-fn parse_statement(tokens: &mut Peekable<Iter<'_, Token>>) -> Statement {
-    let tk = tokens.peek().unwrap();
-    let statement;
+fn parse_statement(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    errors: &mut Vec<ParseError>,
+) -> ParseResult<Statement> {
+    let tk = peek_token(tokens);
     match tk.token_type {
+        TokenType::LBrace => {
+            tokens.next();
+            let blocks = parse_block_items(tokens, errors);
+            let close = expect(tokens, TokenType::RBrace, "`}` to end block")?;
+            Ok(Statement::Block(blocks, tk.span.to(close.span)))
+        }
         TokenType::Return => {
             tokens.next(); // remove return token
-            match tokens.peek().unwrap().token_type {
-                // Return case
-                TokenType::Literal
-                | TokenType::Minus
-                | TokenType::BitComplement
-                | TokenType::LogicalNeg
-                | TokenType::Identifier
-                | TokenType::LParen => statement = Statement::Return(parse_expr(tokens)),
-                _ => {
-                    dbg!(tokens.peek().unwrap());
-                    panic!();
-                }
-            }
-            match tokens.next().unwrap().token_type {
-                TokenType::Semicolon => {}
-                _ => panic!(),
-            }
-        }
-        TokenType::Identifier => {
-            // Expression case
-            statement = Statement::Expr(parse_expr(tokens));
-            match tokens.next().unwrap().token_type {
-                TokenType::Semicolon => {}
-                _ => panic!(),
-            }
+            let expr = match peek_type(tokens) {
+                TokenType::Semicolon => None,
+                _ => Some(parse_expr(tokens)?),
+            };
+            let semi = expect(tokens, TokenType::Semicolon, "`;` after return statement")?;
+            Ok(Statement::Return(expr, tk.span.to(semi.span)))
         }
         TokenType::If => {
             tokens.next();
-            match tokens.next().unwrap().token_type {
-                TokenType::LParen => {}
-                _ => panic!(),
-            }
-
-            let expr = parse_expr(tokens);
-
-            match tokens.next().unwrap().token_type {
-                TokenType::RParen => {}
-                _ => panic!(),
-            }
+            expect(tokens, TokenType::LParen, "`(` after `if`")?;
+            let expr = parse_expr(tokens)?;
+            expect(tokens, TokenType::RParen, "`)` after condition")?;
 
-            let inner_statement = parse_statement(tokens);
+            let inner_statement = parse_statement(tokens, errors)?;
 
-            let else_statement = match tokens.peek().unwrap().token_type {
+            let else_statement = match peek_type(tokens) {
                 TokenType::Else => {
                     tokens.next();
-                    Some(parse_statement(tokens))
+                    Some(parse_statement(tokens, errors)?)
                 }
                 _ => None,
             };
 
-            statement = Statement::If(
+            let span = tk.span.to(else_statement
+                .as_ref()
+                .map_or(inner_statement.span(), Statement::span));
+
+            Ok(Statement::If(
                 expr,
                 Box::new(inner_statement),
-                match else_statement {
-                    Some(s) => Some(Box::new(s)),
-                    None => None,
-                },
-            )
+                else_statement.map(Box::new),
+                span,
+            ))
+        }
+        TokenType::While => {
+            tokens.next();
+            expect(tokens, TokenType::LParen, "`(` after `while`")?;
+            let expr = parse_expr(tokens)?;
+            expect(tokens, TokenType::RParen, "`)` after condition")?;
+            let body = parse_statement(tokens, errors)?;
+            let span = tk.span.to(body.span());
+            Ok(Statement::While(expr, Box::new(body), span))
+        }
+        TokenType::Do => {
+            tokens.next();
+            let body = parse_statement(tokens, errors)?;
+            expect(tokens, TokenType::While, "`while` after do-while body")?;
+            expect(tokens, TokenType::LParen, "`(` after `while`")?;
+            let expr = parse_expr(tokens)?;
+            expect(tokens, TokenType::RParen, "`)` after condition")?;
+            let semi = expect(tokens, TokenType::Semicolon, "`;` after do-while")?;
+            Ok(Statement::DoWhile(
+                Box::new(body),
+                expr,
+                tk.span.to(semi.span),
+            ))
+        }
+        TokenType::For => {
+            tokens.next();
+            expect(tokens, TokenType::LParen, "`(` after `for`")?;
+
+            // A declaration consumes its own `;`.
+            let init = match peek_type(tokens) {
+                tt if is_type_specifier(tt) => {
+                    Some(ForInit::Declaration(parse_declaration(tokens)?))
+                }
+                _ => {
+                    let init = parse_optional_expr(tokens, TokenType::Semicolon)?;
+                    expect(tokens, TokenType::Semicolon, "`;` after for initializer")?;
+                    init.map(ForInit::Expr)
+                }
+            };
+            let condition = parse_optional_expr(tokens, TokenType::Semicolon)?;
+            expect(tokens, TokenType::Semicolon, "`;` after for condition")?;
+            let post = parse_optional_expr(tokens, TokenType::RParen)?;
+            expect(tokens, TokenType::RParen, "`)` after for clauses")?;
+
+            let body = parse_statement(tokens, errors)?;
+            let span = tk.span.to(body.span());
+            Ok(Statement::For(init, condition, post, Box::new(body), span))
+        }
+        TokenType::Switch => {
+            tokens.next();
+            expect(tokens, TokenType::LParen, "`(` after `switch`")?;
+            let expr = parse_expr(tokens)?;
+            expect(tokens, TokenType::RParen, "`)` after switch value")?;
+            let body = parse_statement(tokens, errors)?;
+            let span = tk.span.to(body.span());
+            Ok(Statement::Switch(expr, Box::new(body), span))
+        }
+        TokenType::Case => {
+            tokens.next();
+            // The grammar only allows a conditional expression here.
+            let expr = Expression::Conditional(Box::new(parse_conditional_expr(tokens)?));
+            expect(tokens, TokenType::Colon, "`:` after case value")?;
+            let inner = parse_statement(tokens, errors)?;
+            let span = tk.span.to(inner.span());
+            Ok(Statement::Case(expr, Box::new(inner), span))
+        }
+        TokenType::Default => {
+            tokens.next();
+            expect(tokens, TokenType::Colon, "`:` after `default`")?;
+            let inner = parse_statement(tokens, errors)?;
+            let span = tk.span.to(inner.span());
+            Ok(Statement::Default(Box::new(inner), span))
+        }
+        TokenType::Goto => {
+            tokens.next();
+            let label = expect(tokens, TokenType::Identifier, "label after `goto`")?;
+            let semi = expect(tokens, TokenType::Semicolon, "`;` after goto statement")?;
+            Ok(Statement::Goto(
+                label.text.to_string(),
+                tk.span.to(semi.span),
+            ))
+        }
+        // An identifier followed by `:` labels a statement.
+        TokenType::Identifier
+            if tokens.clone().nth(1).map(|tk| tk.token_type) == Some(TokenType::Colon) =>
+        {
+            tokens.next();
+            tokens.next();
+            let inner = parse_statement(tokens, errors)?;
+            let span = tk.span.to(inner.span());
+            Ok(Statement::Labeled(
+                tk.text.to_string(),
+                Box::new(inner),
+                span,
+            ))
+        }
+        TokenType::Semicolon => {
+            tokens.next();
+            Ok(Statement::Null(tk.span))
+        }
+        TokenType::Break | TokenType::Continue => {
+            tokens.next();
+            let semi = expect(tokens, TokenType::Semicolon, "`;` after jump statement")?;
+            let span = tk.span.to(semi.span);
+            Ok(match tk.token_type {
+                TokenType::Break => Statement::Break(span),
+                _ => Statement::Continue(span),
+            })
         }
         _ => {
-            dbg!(tk);
-            panic!();
+            // Expression case
+            let expr = parse_expr(tokens)?;
+            let semi = expect(tokens, TokenType::Semicolon, "`;` after expression")?;
+            Ok(Statement::Expr(expr, tk.span.to(semi.span)))
         }
     }
+}
+
+fn parse_storage_class<'a>(tokens: &mut Peekable<Iter<'a, Token>>) -> Option<&'a Token> {
+    match peek_type(tokens) {
+        TokenType::Static | TokenType::Extern => Some(next_token(tokens)),
+        _ => None,
+    }
+}
+
+fn is_type_specifier(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Void
+            | TokenType::Char
+            | TokenType::Short
+            | TokenType::Integer
+            | TokenType::Long
+            | TokenType::Signed
+            | TokenType::Unsigned
+            | TokenType::Struct
+            | TokenType::Union
+            | TokenType::Enum
+    )
+}
 
-    statement
+// Parses type specifiers followed by any number of `*`s.
+fn parse_type(tokens: &mut Peekable<Iter<'_, Token>>, expected: &str) -> ParseResult<(Type, Span)> {
+    let (ty, span) = parse_specifiers(tokens, expected)?;
+    parse_pointers(tokens, ty, span)
 }
 
-fn parse_declaration(tokens: &mut Peekable<Iter<'_, Token>>) -> Declaration {
-    tokens.next(); // get rid of type
-    let identifier = tokens.next().unwrap().text.as_str();
-    tokens.next(); // get rid of equals
-    let decl = Declaration {
-        identifier: String::from(identifier),
-        expr: match tokens.peek().unwrap().token_type {
-            TokenType::Semicolon => None,
-            _ => Some(parse_expr(tokens)),
-        },
+// Parses a list of type specifiers, which may come in any order, such as
+// `unsigned long int` or `long unsigned`, or a struct, union or enum
+// specifier.
+fn parse_specifiers(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    expected: &str,
+) -> ParseResult<(Type, Span)> {
+    let first = peek_token(tokens);
+    if matches!(
+        first.token_type,
+        TokenType::Struct | TokenType::Union | TokenType::Enum
+    ) {
+        return parse_tag_specifier(tokens);
+    }
+    if !is_type_specifier(first.token_type) {
+        return Err(unexpected(expected, first));
+    }
+
+    let mut specifiers: Vec<&Token> = Vec::new();
+    while is_type_specifier(peek_type(tokens)) {
+        specifiers.push(next_token(tokens));
+    }
+    let span = first.span.to(specifiers.last().unwrap().span);
+    let count = |token_type| {
+        specifiers
+            .iter()
+            .filter(|tk| tk.token_type == token_type)
+            .count()
+    };
+    let (chars, shorts, ints, longs) = (
+        count(TokenType::Char),
+        count(TokenType::Short),
+        count(TokenType::Integer),
+        count(TokenType::Long),
+    );
+    let (signed, unsigned) = (count(TokenType::Signed), count(TokenType::Unsigned));
+    let voids = count(TokenType::Void);
+
+    let valid = (voids == 0 || specifiers.len() == 1)
+        && signed + unsigned <= 1
+        && ints <= 1
+        && match (chars, shorts, longs) {
+            (1, 0, 0) => ints == 0,
+            (0, 1, 0) | (0, 0, 1) | (0, 0, 2) | (0, 0, 0) => true,
+            _ => false,
+        };
+    if !valid {
+        let text: Vec<&str> = specifiers.iter().map(|tk| tk.text.as_str()).collect();
+        return Err(ParseError {
+            expected: String::from("a valid combination of type specifiers"),
+            found: format!("`{}`", text.join(" ")),
+            span,
+            insert: None,
+        });
+    }
+
+    let ty = match (chars, shorts, longs) {
+        _ if voids == 1 => Type::Void,
+        (1, _, _) => Type::Char,
+        (_, 1, _) => Type::Short,
+        (_, _, 1) => Type::Long,
+        (_, _, 2) => Type::LongLong,
+        _ => Type::Int,
     };
+    let ty = match (ty, unsigned) {
+        (Type::Char, 1) => Type::UChar,
+        (Type::Short, 1) => Type::UShort,
+        (Type::Long, 1) => Type::ULong,
+        (Type::LongLong, 1) => Type::ULongLong,
+        (Type::Int, 1) => Type::UInt,
+        (ty, _) => ty,
+    };
+    Ok((ty, span))
+}
+
+// Parses `struct`, `union` or `enum`, an optional tag and, for a definition,
+// the member declarations or enumerators in braces.
+fn parse_tag_specifier(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<(Type, Span)> {
+    let keyword = next_token(tokens);
+    let tag = tokens.next_if(|tk| tk.token_type == TokenType::Identifier);
+    let mut span = keyword.span.to(tag.map_or(keyword.span, |tk| tk.span));
+
+    let body = if peek_type(tokens) == TokenType::LBrace {
+        tokens.next();
+        let body = if keyword.token_type == TokenType::Enum {
+            TagBody::Enumerators(parse_enumerators(tokens)?)
+        } else {
+            let mut fields = Vec::new();
+            while !matches!(peek_type(tokens), TokenType::RBrace | TokenType::Eof) {
+                parse_fields(tokens, &mut fields)?;
+            }
+            TagBody::Fields(fields)
+        };
+        let expected = match body {
+            TagBody::Fields(_) => "`}` after members",
+            TagBody::Enumerators(_) => "`}` after enumerators",
+        };
+        let close = expect(tokens, TokenType::RBrace, expected)?;
+        span = span.to(close.span);
+        Some(body)
+    } else if tag.is_none() {
+        let expected = format!("tag name or `{{` after `{}`", keyword.text);
+        return Err(unexpected(&expected, peek_token(tokens)));
+    } else {
+        None
+    };
+
+    let specifier = TagSpecifier {
+        kind: keyword.token_type,
+        tag: tag.map(|tk| tk.text.to_string()),
+        body,
+        span,
+    };
+    Ok((Type::Specifier(Rc::new(specifier)), span))
+}
 
-    match tokens.next().unwrap().token_type {
-        TokenType::Semicolon => {}
-        _ => panic!(),
+// Parses the comma-separated enumerators of an enum definition, which may
+// end with a comma.
+fn parse_enumerators(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Vec<Enumerator>> {
+    let mut enumerators = Vec::new();
+    loop {
+        let name = expect(tokens, TokenType::Identifier, "enumerator name")?;
+        let mut span = name.span;
+        let value = match tokens.next_if(|tk| tk.token_type == TokenType::Assign) {
+            Some(_) => {
+                let value = parse_conditional_expr(tokens)?;
+                span = span.to(value.span);
+                Some(Expression::Conditional(Box::new(value)))
+            }
+            None => None,
+        };
+        enumerators.push(Enumerator {
+            name: name.text.to_string(),
+            value,
+            span,
+        });
+        if tokens
+            .next_if(|tk| tk.token_type == TokenType::Comma)
+            .is_none()
+            || peek_type(tokens) == TokenType::RBrace
+        {
+            return Ok(enumerators);
+        }
     }
+}
 
-    decl
+// Parses one member declaration, which may declare several members as in
+// `int x, *next;`. A struct or union definition without a tag or a name is
+// an anonymous member.
+fn parse_fields(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    fields: &mut Vec<Field>,
+) -> ParseResult<()> {
+    let (ty, type_span) = parse_specifiers(tokens, "member type")?;
+    let anonymous = matches!(
+        &ty,
+        Type::Specifier(specifier)
+            if specifier.tag.is_none() && matches!(specifier.body, Some(TagBody::Fields(_)))
+    );
+    if anonymous && peek_type(tokens) == TokenType::Semicolon {
+        tokens.next();
+        fields.push(Field {
+            name: None,
+            ty,
+            dims: Vec::new(),
+            span: type_span,
+        });
+        return Ok(());
+    }
+
+    loop {
+        let (member_ty, _) = parse_pointers(tokens, ty.clone(), type_span)?;
+        let name = expect(tokens, TokenType::Identifier, "member name")?;
+        let dims = parse_dims(tokens)?;
+        fields.push(Field {
+            name: Some(name.text.to_string()),
+            ty: member_ty,
+            dims,
+            span: name.span,
+        });
+        if peek_type(tokens) != TokenType::Comma {
+            break;
+        }
+        tokens.next();
+    }
+    expect(tokens, TokenType::Semicolon, "`;` after member declaration")?;
+    Ok(())
 }
 
-fn parse_block(tokens: &mut Peekable<Iter<'_, Token>>) -> BlockItem {
-    match tokens.peek().unwrap().token_type {
-        TokenType::Integer => BlockItem::Declaration(parse_declaration(tokens)),
-        _ => BlockItem::Statement(parse_statement(tokens)),
+// Parses the `*`s of a declarator, applying them to `ty`.
+fn parse_pointers(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    mut ty: Type,
+    mut span: Span,
+) -> ParseResult<(Type, Span)> {
+    while peek_type(tokens) == TokenType::Multiplication {
+        ty = Type::Pointer(Box::new(ty));
+        span = span.to(next_token(tokens).span);
     }
+    Ok((ty, span))
 }
 
-fn parse_fn(tokens: &mut Peekable<Iter<'_, Token>>) -> Function {
-    let name;
+// Parses the `[size]` suffixes of an array declarator.
+fn parse_dims(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Vec<Option<Expression>>> {
+    let mut dims = Vec::new();
+    while peek_type(tokens) == TokenType::LBracket {
+        tokens.next();
+        if peek_type(tokens) == TokenType::RBracket {
+            dims.push(None);
+        } else {
+            let size = parse_conditional_expr(tokens)?;
+            dims.push(Some(Expression::Conditional(Box::new(size))));
+        }
+        expect(tokens, TokenType::RBracket, "`]` after array size")?;
+    }
+    Ok(dims)
+}
 
-    // Handle return type, function identifier, and left parenthesis.
-    match tokens.next().unwrap().token_type {
-        TokenType::Integer => {
-            let tk = tokens.next().unwrap();
-            match (tk.token_type, tokens.next().unwrap().token_type) {
-                (TokenType::Identifier, TokenType::LParen) => {
-                    name = tk.text.to_string();
-                }
-                _ => panic!(),
+fn parse_initializer(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Initializer> {
+    if peek_type(tokens) != TokenType::LBrace {
+        return Ok(Initializer::Expr(parse_expr(tokens)?));
+    }
+
+    // A trailing comma is allowed before the `}`.
+    let open = next_token(tokens);
+    let mut items = Vec::new();
+    while peek_type(tokens) != TokenType::RBrace {
+        let designators = parse_designators(tokens)?;
+        items.push((designators, parse_initializer(tokens)?));
+        if peek_type(tokens) != TokenType::Comma {
+            break;
+        }
+        tokens.next();
+    }
+    let close = expect(tokens, TokenType::RBrace, "`}` after initializer list")?;
+    Ok(Initializer::List(items, open.span.to(close.span)))
+}
+
+// Parses the designators before an item of an initializer list, such as
+// `.pos.x =` or `[2] =`, if there are any.
+fn parse_designators(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Vec<Designator>> {
+    let mut designators = Vec::new();
+    loop {
+        match peek_type(tokens) {
+            TokenType::Dot => {
+                let dot = next_token(tokens);
+                let name = expect(tokens, TokenType::Identifier, "member name after `.`")?;
+                let span = dot.span.to(name.span);
+                designators.push(Designator::Member(name.text.to_string(), span));
+            }
+            TokenType::LBracket => {
+                let open = next_token(tokens);
+                let index = parse_conditional_expr(tokens)?;
+                let close = expect(tokens, TokenType::RBracket, "`]` after array designator")?;
+                let index = Expression::Conditional(Box::new(index));
+                designators.push(Designator::Index(index, open.span.to(close.span)));
             }
+            _ => break,
         }
-        _ => panic!(),
     }
+    if !designators.is_empty() {
+        expect(tokens, TokenType::Assign, "`=` after designator")?;
+    }
+    Ok(designators)
+}
 
-    // Handle function arguments, right parenthesis, and left brace.
+// Skips what `parse_type` would parse, with any storage class before it,
+// for lookahead.
+fn skip_type(ahead: &mut Peekable<Iter<'_, Token>>) {
+    parse_storage_class(ahead);
+    while let Some(tk) = ahead.next_if(|tk| is_type_specifier(tk.token_type)) {
+        if !matches!(
+            tk.token_type,
+            TokenType::Struct | TokenType::Union | TokenType::Enum
+        ) {
+            continue;
+        }
+        ahead.next_if(|tk| tk.token_type == TokenType::Identifier);
+        if ahead
+            .next_if(|tk| tk.token_type == TokenType::LBrace)
+            .is_none()
+        {
+            continue;
+        }
+        let mut depth = 1;
+        while depth > 0 {
+            match ahead.next().map(|tk| tk.token_type) {
+                Some(TokenType::LBrace) => depth += 1,
+                Some(TokenType::RBrace) => depth -= 1,
+                Some(TokenType::Eof) | None => return,
+                _ => {}
+            }
+        }
+    }
+    while ahead
+        .next_if(|tk| tk.token_type == TokenType::Multiplication)
+        .is_some()
+    {}
+}
+
+// Whether the declaration ahead declares only a tag, with nothing after the
+// type but a `;`.
+fn is_tag_declaration(tokens: &mut Peekable<Iter<'_, Token>>) -> bool {
+    let mut ahead = tokens.clone();
+    skip_type(&mut ahead);
+    ahead.peek().map(|tk| tk.token_type) == Some(TokenType::Semicolon)
+}
+
+fn parse_tag_declaration(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<(Type, Span)> {
+    // A storage class does nothing for a tag.
+    parse_storage_class(tokens);
+    let (ty, span) = parse_type(tokens, "type in declaration")?;
+    let semi = expect(tokens, TokenType::Semicolon, "`;` after declaration")?;
+    Ok((ty, span.to(semi.span)))
+}
+
+// Parses a declaration, which may declare several variables as in
+// `int i = 0, a[3], *p;`. Each declaration spans its declarator and
+// initializer, except that the first starts at the specifiers and the last
+// ends at the `;`.
+fn parse_declaration(tokens: &mut Peekable<Iter<'_, Token>>) -> ParseResult<Vec<Declaration>> {
+    let storage = parse_storage_class(tokens);
+    let (ty, type_span) = parse_specifiers(tokens, "type in declaration")?;
+    let mut start = storage.map_or(type_span, |tk| tk.span);
+
+    let mut declarations = Vec::new();
     loop {
-        let tk = tokens.next().unwrap();
-        match tk.token_type {
-            TokenType::RParen => match tokens.next().unwrap().token_type {
-                TokenType::LBrace => break,
-                _ => panic!(),
-            },
-            _ => panic!(),
+        let (declarator_ty, _) = parse_pointers(tokens, ty.clone(), type_span)?;
+        let identifier = expect(tokens, TokenType::Identifier, "identifier in declaration")?;
+        let dims = parse_dims(tokens)?;
+
+        let init = match peek_type(tokens) {
+            TokenType::Assign => {
+                tokens.next();
+                Some(parse_initializer(tokens)?)
+            }
+            _ => None,
+        };
+        let end = init.as_ref().map_or(identifier.span, Initializer::span);
+        declarations.push(Declaration {
+            identifier: identifier.text.to_string(),
+            storage: storage.map(|tk| tk.token_type),
+            ty: declarator_ty,
+            dims,
+            init,
+            span: start.to(end),
+        });
+
+        if peek_type(tokens) != TokenType::Comma {
+            break;
         }
+        tokens.next();
+        start = peek_token(tokens).span;
     }
 
+    let semi = expect(tokens, TokenType::Semicolon, "`;` after declaration")?;
+    let last = declarations.last_mut().unwrap();
+    last.span = last.span.to(semi.span);
+    Ok(declarations)
+}
+
+fn parse_block(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    errors: &mut Vec<ParseError>,
+) -> ParseResult<BlockItem> {
+    match peek_type(tokens) {
+        tt if tt == TokenType::Static || tt == TokenType::Extern || is_type_specifier(tt) => {
+            if is_tag_declaration(tokens) {
+                let (ty, span) = parse_tag_declaration(tokens)?;
+                return Ok(BlockItem::Tag(ty, span));
+            }
+            Ok(BlockItem::Declaration(parse_declaration(tokens)?))
+        }
+        _ => Ok(BlockItem::Statement(parse_statement(tokens, errors)?)),
+    }
+}
+
+// Parses block items up to (but not including) the closing `}`, recovering
+// from errors at the next statement.
+fn parse_block_items(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    errors: &mut Vec<ParseError>,
+) -> Vec<BlockItem> {
     let mut blocks = Vec::new();
 
-    while let Some(tk) = tokens.peek() {
-        match tk.token_type {
-            TokenType::RBrace => break,
-            _ => blocks.push(parse_block(tokens)),
+    loop {
+        match peek_type(tokens) {
+            TokenType::RBrace | TokenType::Eof => break,
+            _ => match parse_block(tokens, errors) {
+                Ok(block) => blocks.push(block),
+                Err(err) => {
+                    errors.push(err);
+                    synchronize(tokens);
+                }
+            },
         }
     }
 
-    tokens.next();
+    blocks
+}
+
+fn parse_fn(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    errors: &mut Vec<ParseError>,
+) -> ParseResult<Function> {
+    // Handle storage class, return type, function identifier, and left
+    // parenthesis.
+    let storage = parse_storage_class(tokens);
+    let (return_type, type_span) = parse_type(tokens, "return type")?;
+    let name = expect(tokens, TokenType::Identifier, "function name")?;
+    expect(tokens, TokenType::LParen, "`(` after function name")?;
+
+    // Handle function parameters and right parenthesis. `(void)` declares a
+    // function without parameters, while `()` leaves them unspecified.
+    let mut params = Vec::new();
+    let mut unnamed = None;
+    let prototype = peek_type(tokens) != TokenType::RParen;
+    if peek_type(tokens) == TokenType::Void
+        && tokens.clone().nth(1).map(|tk| tk.token_type) == Some(TokenType::RParen)
+    {
+        tokens.next();
+    } else if prototype {
+        loop {
+            let (ty, type_span) = parse_type(tokens, "parameter type")?;
+            let (name, span) = if peek_type(tokens) == TokenType::Identifier {
+                let param = next_token(tokens);
+                (Some(param.text.to_string()), param.span)
+            } else {
+                unnamed.get_or_insert_with(|| unexpected("parameter name", peek_token(tokens)));
+                (None, type_span)
+            };
+            let dims = parse_dims(tokens)?;
+            params.push(Param {
+                name,
+                ty,
+                dims,
+                span,
+            });
+            if peek_type(tokens) != TokenType::Comma {
+                break;
+            }
+            tokens.next();
+        }
+    }
+    expect(tokens, TokenType::RParen, "`)`")?;
+    let params = prototype.then_some(params);
+
+    if peek_type(tokens) == TokenType::Semicolon {
+        let semi = next_token(tokens);
+        return Ok(Function {
+            name: name.text.to_string(),
+            storage: storage.map(|tk| tk.token_type),
+            return_type,
+            params,
+            blocks: None,
+            span: storage.map_or(type_span, |tk| tk.span).to(semi.span),
+        });
+    }
 
-    Function { name, blocks }
+    // Parameters of a definition must be named.
+    if let Some(err) = unnamed {
+        return Err(err);
+    }
+    expect(
+        tokens,
+        TokenType::LBrace,
+        "`{` to start function body, or `;`",
+    )?;
+
+    let blocks = parse_block_items(tokens, errors);
+    let close = expect(tokens, TokenType::RBrace, "`}` to end function body")?;
+
+    Ok(Function {
+        name: name.text.to_string(),
+        storage: storage.map(|tk| tk.token_type),
+        return_type,
+        params,
+        blocks: Some(blocks),
+        span: storage.map_or(type_span, |tk| tk.span).to(close.span),
+    })
 }
 
-pub fn parse(tokens: &Vec<Token>) -> Program {
-    let mut prog = Program {
-        functions: Vec::new(),
-    };
+fn parse_top_level(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    errors: &mut Vec<ParseError>,
+) -> ParseResult<TopLevel> {
+    // `<type> <id> (` starts a function; anything else declares a variable,
+    // or just a tag.
+    if is_tag_declaration(tokens) {
+        let (ty, span) = parse_tag_declaration(tokens)?;
+        return Ok(TopLevel::Tag(ty, span));
+    }
+    let mut ahead = tokens.clone();
+    skip_type(&mut ahead);
+    if ahead.nth(1).map(|tk| tk.token_type) == Some(TokenType::LParen) {
+        Ok(TopLevel::Function(parse_fn(tokens, errors)?))
+    } else {
+        Ok(TopLevel::Declaration(parse_declaration(tokens)?))
+    }
+}
 
-    prog.functions.push(parse_fn(&mut tokens.iter().peekable()));
+pub fn parse(tokens: &[Token]) -> Result<Program, Vec<ParseError>> {
+    let mut prog = Program { items: Vec::new() };
+    let mut errors = Vec::new();
+
+    let mut tokens = tokens.iter().peekable();
+    while peek_type(&mut tokens) != TokenType::Eof {
+        // A `;` on its own is an empty declaration, such as one after a
+        // function body.
+        if tokens
+            .next_if(|tk| tk.token_type == TokenType::Semicolon)
+            .is_some()
+        {
+            continue;
+        }
+        let start = tokens.clone();
+        match parse_top_level(&mut tokens, &mut errors) {
+            Ok(item) => prog.items.push(item),
+            Err(err) => {
+                errors.push(err);
+                // The braces opened by the tokens the definition consumed.
+                let consumed = start.len() - tokens.len();
+                let depth = start
+                    .take(consumed)
+                    .fold(0, |depth: usize, tk| match tk.token_type {
+                        TokenType::LBrace => depth + 1,
+                        TokenType::RBrace => depth.saturating_sub(1),
+                        _ => depth,
+                    });
+                skip_definition(&mut tokens, depth);
+            }
+        }
+    }
 
-    prog
+    if errors.is_empty() {
+        Ok(prog)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The syntax errors in `source`, as `<line>:<column> <message>`.
+    fn errors(source: &str) -> Vec<String> {
+        let (tokens, _) = lexer::lex(source, 0);
+        match parse(&tokens) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors
+                .iter()
+                .map(|err| format!("{}:{} {}", err.span.line, err.span.col, err))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn recovers_inside_nested_block() {
+        let source = "\
+int main(void) {
+    int x = 1;
+    if (x { x = 2; }
+    while (x) {
+        break }
+    return x;
+}
+";
+        assert_eq!(
+            errors(source),
+            [
+                "3:11 expected `)` after condition, found `{`",
+                "5:15 expected `;` after jump statement, found `}`",
+            ]
+        );
+    }
+
+    #[test]
+    fn recovers_at_next_statement() {
+        let source = "\
+int main(void) {
+    int x = 1 +
+    return x;
+    x = ;
+    for (;;) { x = 2 3; }
+    return 0;
+}
+int f(void) { return 1 }
+";
+        assert_eq!(
+            errors(source),
+            [
+                "3:5 expected expression, found keyword `return`",
+                "4:9 expected expression, found `;`",
+                "5:22 expected `;` after expression, found literal `3`",
+                "8:24 expected `;` after return statement, found `}`",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_each_function_once() {
+        let source = "\
+int f(void) { { { x = ; } } }
+int g(void) { return 1; }
+int h(void) { return ) }
+";
+        assert_eq!(
+            errors(source),
+            [
+                "1:23 expected expression, found `;`",
+                "3:22 expected expression, found `)`",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_null_statements() {
+        let source = "\
+int main(void) {
+    for (;;) ;
+    while (1) ;
+    switch (1) { default: ; }
+out: ;
+}
+";
+        assert_eq!(errors(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn parses_empty_declarations() {
+        let source = "\
+;
+struct s { int x; };;
+int f(void) { return 1; };
+int main(void) { return f(); }
+";
+        assert_eq!(errors(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn recovers_after_the_braces_of_a_bad_definition() {
+        let source = "\
+struct s { int x int y; };
+int a[2][2] = {{1, 2} {3, 4}};
+int f(void) { return 1 }
+struct t { int x; } t = {1 2};
+int main(void) { return f(); }
+";
+        assert_eq!(
+            errors(source),
+            [
+                "1:18 expected `;` after member declaration, found keyword `int`",
+                "2:23 expected `}` after initializer list, found `{`",
+                "3:24 expected `;` after return statement, found `}`",
+                "4:28 expected `}` after initializer list, found literal `2`",
+            ]
+        );
+    }
 }
@@ -0,0 +1,56 @@
+// A region of source text. `start` and `end` are byte offsets into the file,
+// `line` and `col` are the 1-based position of `start`.
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Span {
+    pub file: usize,
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub col: u32,
+}
+
+impl Span {
+    // Span starting at `self` and extending to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end.max(self.end),
+            ..self
+        }
+    }
+}
+
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+}
+
+// Owns every file being compiled; `Span::file` is an index into `files`.
+#[derive(Default)]
+pub struct SourceMap {
+    pub files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn add(&mut self, name: &str, text: String) -> usize {
+        self.files.push(SourceFile {
+            name: name.to_string(),
+            text,
+        });
+        self.files.len() - 1
+    }
+
+    pub fn get(&self, id: usize) -> &SourceFile {
+        &self.files[id]
+    }
+}
+
+impl SourceFile {
+    // Text of the 1-based line `line`, without its newline.
+    pub fn line(&self, line: u32) -> &str {
+        self.text
+            .split('\n')
+            .nth(line as usize - 1)
+            .unwrap_or("")
+            .trim_end_matches('\r')
+    }
+}
@@ -0,0 +1,353 @@
+// C types, and the conversions between them (C11 6.3). Sizes and alignments
+// follow the System V AMD64 ABI, where plain `char` is signed.
+use crate::lexer::IntKind;
+use crate::parser::TagSpecifier;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Type {
+    // Only usable as the target of a pointer.
+    Void,
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Long,
+    ULong,
+    LongLong,
+    ULongLong,
+    Pointer(Box<Type>),
+    // Element type and length.
+    Array(Box<Type>, usize),
+    // A struct or union.
+    Struct(Rc<Aggregate>),
+    // A `struct` or `union` specifier as written. Tags are scoped like
+    // variables, so the generator resolves these to a `Struct` before using
+    // the type.
+    Specifier(Rc<TagSpecifier>),
+}
+
+// The definition of a struct or union. Each definition is a distinct type,
+// so they compare by identity.
+pub struct Aggregate {
+    pub union: bool,
+    pub tag: Option<String>,
+    // None until the definition is seen.
+    layout: RefCell<Option<Layout>>,
+}
+
+struct Layout {
+    members: Vec<Member>,
+    size: i32,
+    align: i32,
+}
+
+#[derive(Clone)]
+pub struct Member {
+    // None for an anonymous struct or union, whose members are accessed as if
+    // they were members of the containing one.
+    pub name: Option<String>,
+    pub ty: Type,
+    pub offset: i32,
+}
+
+impl Aggregate {
+    pub fn new(union: bool, tag: Option<String>) -> Aggregate {
+        Aggregate {
+            union,
+            tag,
+            layout: RefCell::new(None),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.layout.borrow().is_some()
+    }
+
+    // Lays out the members in order, each at the next offset suitably
+    // aligned for it, or all at offset 0 in a union. The size is rounded up
+    // to the strictest alignment, so arrays of the type stay aligned.
+    pub fn define(&self, members: Vec<(Option<String>, Type)>) {
+        let (mut size, mut align) = (0, 1);
+        let members = members
+            .into_iter()
+            .map(|(name, ty)| {
+                let offset = if self.union {
+                    0
+                } else {
+                    align_to(size, ty.align())
+                };
+                size = size.max(offset + ty.size());
+                align = align.max(ty.align());
+                Member { name, ty, offset }
+            })
+            .collect();
+        *self.layout.borrow_mut() = Some(Layout {
+            members,
+            size: align_to(size, align),
+            align,
+        });
+    }
+
+    pub fn members(&self) -> Vec<Member> {
+        self.layout
+            .borrow()
+            .as_ref()
+            .map_or(Vec::new(), |layout| layout.members.clone())
+    }
+
+    // The member called `name`, looking inside anonymous members, with its
+    // offset from the start of this type. The index is that of the member
+    // of this type containing it.
+    pub fn find(&self, name: &str) -> Option<(usize, Member)> {
+        self.members()
+            .into_iter()
+            .enumerate()
+            .find_map(|(i, member)| match (&member.name, &member.ty) {
+                (Some(member_name), _) if member_name == name => Some((i, member)),
+                (None, Type::Struct(inner)) => {
+                    let (_, found) = inner.find(name)?;
+                    let offset = member.offset + found.offset;
+                    Some((i, Member { offset, ..found }))
+                }
+                _ => None,
+            })
+    }
+
+    fn size(&self) -> i32 {
+        self.layout
+            .borrow()
+            .as_ref()
+            .map_or(0, |layout| layout.size)
+    }
+
+    fn align(&self) -> i32 {
+        self.layout
+            .borrow()
+            .as_ref()
+            .map_or(1, |layout| layout.align)
+    }
+}
+
+impl PartialEq for Aggregate {
+    fn eq(&self, other: &Aggregate) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for Aggregate {}
+
+// Members are left out, as a struct may contain pointers to itself.
+impl fmt::Debug for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keyword = if self.union { "union" } else { "struct" };
+        match &self.tag {
+            Some(tag) => write!(f, "{} {}", keyword, tag),
+            None => write!(f, "{} <anonymous>", keyword),
+        }
+    }
+}
+
+fn align_to(offset: i32, align: i32) -> i32 {
+    (offset + align - 1) / align * align
+}
+
+impl Type {
+    pub fn size(&self) -> i32 {
+        match self {
+            // Arithmetic on `void *` steps by bytes, as a GNU extension.
+            Type::Void | Type::Char | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt => 4,
+            Type::Long | Type::ULong | Type::LongLong | Type::ULongLong | Type::Pointer(_) => 8,
+            Type::Array(element, length) => element.size() * *length as i32,
+            Type::Struct(aggregate) => aggregate.size(),
+            Type::Specifier(_) => unreachable!("struct types are resolved before use"),
+        }
+    }
+
+    pub fn align(&self) -> i32 {
+        match self {
+            Type::Array(element, _) => element.align(),
+            Type::Struct(aggregate) => aggregate.align(),
+            _ => self.size(),
+        }
+    }
+
+    // Whether objects of this type can be created: not `void`, a struct
+    // without a definition, or an array without a length.
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Type::Void => false,
+            Type::Array(element, length) => *length > 0 && element.is_complete(),
+            Type::Struct(aggregate) => aggregate.is_complete(),
+            _ => true,
+        }
+    }
+
+    // Arithmetic and pointer types, which fit in a register.
+    pub fn is_scalar(&self) -> bool {
+        !matches!(
+            self,
+            Type::Void | Type::Array(..) | Type::Struct(_) | Type::Specifier(_)
+        )
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.is_scalar() && !self.is_pointer()
+    }
+
+    // Pointers compare as unsigned addresses.
+    pub fn is_unsigned(&self) -> bool {
+        matches!(
+            self,
+            Type::UChar
+                | Type::UShort
+                | Type::UInt
+                | Type::ULong
+                | Type::ULongLong
+                | Type::Pointer(_)
+        )
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, Type::Pointer(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, Type::Array(..))
+    }
+
+    // What an expression of this type becomes when used as a value: arrays
+    // decay to a pointer to their first element.
+    pub fn decay(self) -> Type {
+        match self {
+            Type::Array(element, _) => Type::Pointer(element),
+            _ => self,
+        }
+    }
+
+    // The element type of an array, and its length with any nested array
+    // dimensions.
+    fn dims(&self) -> (&Type, String) {
+        match self {
+            Type::Array(element, length) => {
+                let (base, dims) = element.dims();
+                (base, format!("[{}]{}", length, dims))
+            }
+            _ => (self, String::new()),
+        }
+    }
+
+    // The type a pointer points to.
+    pub fn pointee(&self) -> Option<&Type> {
+        match self {
+            Type::Pointer(inner) => Some(inner),
+            _ => None,
+        }
+    }
+
+    // The integer conversion rank (C11 6.3.1.1), ignoring signedness.
+    fn rank(&self) -> u8 {
+        match self {
+            Type::Void => 0,
+            Type::Char | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt => 3,
+            Type::Long | Type::ULong | Type::Pointer(_) => 4,
+            Type::LongLong | Type::ULongLong => 5,
+            Type::Array(..) | Type::Struct(_) | Type::Specifier(_) => 0,
+        }
+    }
+
+    fn to_unsigned(&self) -> Type {
+        match self {
+            Type::Char => Type::UChar,
+            Type::Short => Type::UShort,
+            Type::Int => Type::UInt,
+            Type::Long => Type::ULong,
+            Type::LongLong => Type::ULongLong,
+            _ => self.clone(),
+        }
+    }
+
+    // The integer promotions: everything narrower than `int` fits in one.
+    pub fn promote(&self) -> Type {
+        match self {
+            Type::Char | Type::UChar | Type::Short | Type::UShort => Type::Int,
+            _ => self.clone(),
+        }
+    }
+
+    // The usual arithmetic conversions: the type both operands of a binary
+    // operator are converted to.
+    pub fn common(a: &Type, b: &Type) -> Type {
+        let (a, b) = (a.promote(), b.promote());
+        if a == b {
+            return a;
+        }
+        let (high, low) = if a.rank() >= b.rank() { (a, b) } else { (b, a) };
+        if high.is_unsigned() == low.is_unsigned() || high.is_unsigned() {
+            high
+        } else if high.size() > low.size() {
+            // The signed type is of higher rank and can hold every value of
+            // the unsigned one.
+            high
+        } else {
+            high.to_unsigned()
+        }
+    }
+}
+
+impl From<IntKind> for Type {
+    fn from(kind: IntKind) -> Type {
+        match kind {
+            IntKind::Int => Type::Int,
+            IntKind::UInt => Type::UInt,
+            IntKind::Long => Type::Long,
+            IntKind::ULong => Type::ULong,
+            IntKind::LongLong => Type::LongLong,
+            IntKind::ULongLong => Type::ULongLong,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Void => write!(f, "void"),
+            Type::Char => write!(f, "char"),
+            Type::UChar => write!(f, "unsigned char"),
+            Type::Short => write!(f, "short"),
+            Type::UShort => write!(f, "unsigned short"),
+            Type::Int => write!(f, "int"),
+            Type::UInt => write!(f, "unsigned int"),
+            Type::Long => write!(f, "long"),
+            Type::ULong => write!(f, "unsigned long"),
+            Type::LongLong => write!(f, "long long"),
+            Type::ULongLong => write!(f, "unsigned long long"),
+            Type::Pointer(inner) if inner.is_pointer() => write!(f, "{}*", inner),
+            Type::Pointer(inner) if inner.is_array() => {
+                let (base, dims) = inner.dims();
+                write!(f, "{} (*){}", base, dims)
+            }
+            Type::Pointer(inner) => write!(f, "{} *", inner),
+            Type::Array(..) => {
+                let (base, dims) = self.dims();
+                write!(f, "{}{}", base, dims)
+            }
+            Type::Struct(aggregate) => write!(f, "{}", aggregate),
+            Type::Specifier(specifier) => write!(f, "{}", specifier),
+        }
+    }
+}
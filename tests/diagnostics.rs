@@ -0,0 +1,456 @@
+// Runs crust on small erroneous programs and checks what it reports.
+use std::env;
+use std::fs;
+use std::process::Command;
+
+// Compiles `source` with the extra `args` and returns each diagnostic as
+// `<code> <line>:<column> <message>`, in the order they were reported.
+fn diagnose(name: &str, source: &str, args: &[&str]) -> Vec<String> {
+    let dir = env::temp_dir().join(format!("crust-diagnostics-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("test.c"), source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_crust"))
+        .args(args)
+        .args(["--diagnostics-format", "json", "test.c"])
+        .current_dir(&dir)
+        .output()
+        .expect("failed to run crust");
+    fs::remove_dir_all(&dir).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stderr
+        .lines()
+        .map(|line| {
+            let field = |key: &str, end: &str| {
+                let start = line.find(key).unwrap() + key.len();
+                let len = line[start..].find(end).unwrap();
+                line[start..start + len].to_string()
+            };
+            format!(
+                "{} {}:{} {}",
+                field("\"code\":\"", "\""),
+                field("\"line\":", ","),
+                field("\"column\":", ","),
+                field("\"message\":\"", "\",\"span\"")
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn unknown_characters() {
+    let source = "\
+int main() {
+    int $x = 1;
+    return 1 @ 2;
+}
+";
+    assert_eq!(
+        diagnose("unknown_characters", source, &[]),
+        [
+            "E0005 2:9 unknown character '$'",
+            "E0005 3:14 unknown character '@'",
+        ]
+    );
+}
+
+#[test]
+fn bad_case_labels() {
+    let source = "\
+int main() {
+    int x = 2;
+    switch (x) {
+    case 1: break;
+    case 2: case 1: break;
+    case x: break;
+    default: break;
+    default: break;
+    }
+    case 3: return 1;
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("bad_case_labels", source, &[]),
+        [
+            "E0013 5:13 duplicate case value `1`",
+            "E0014 6:10 case label does not reduce to an integer constant",
+            "E0013 8:5 multiple default labels in one switch",
+            "E0012 10:5 `case` label not within a switch statement",
+        ]
+    );
+}
+
+#[test]
+fn bad_labels() {
+    let source = "\
+int main() {
+    goto missing;
+done:
+    return 0;
+done:
+    return 1;
+}
+";
+    assert_eq!(
+        diagnose("bad_labels", source, &[]),
+        [
+            "E0016 5:1 redefinition of label `done`",
+            "E0015 2:5 use of undeclared label `missing`",
+        ]
+    );
+}
+
+#[test]
+fn redefined_function() {
+    let source = "\
+int f() { return 1; }
+int main() { return 0; }
+int f() { return 2; }
+";
+    assert_eq!(
+        diagnose("redefined_function", source, &[]),
+        ["E0003 3:1 redefinition of function `f`"]
+    );
+}
+
+#[test]
+fn mismatched_prototypes() {
+    let source = "\
+int f(int a, int b);
+int f(int a);
+int g(int a, int b);
+int main() {
+    g(1);
+    g(1, 2, 3);
+    return h();
+}
+";
+    assert_eq!(
+        diagnose("mismatched_prototypes", source, &["--std", "c17"]),
+        [
+            "E0018 2:1 conflicting types for `f`",
+            "E0019 5:5 too few arguments to function `g`",
+            "E0019 6:5 too many arguments to function `g`",
+            "E0017 7:12 call to undeclared function `h`",
+        ]
+    );
+    assert_eq!(
+        diagnose("mismatched_prototypes_c89", source, &["--std", "c89"])[3],
+        "W0005 7:12 implicit declaration of function `h`"
+    );
+}
+
+#[test]
+fn bad_globals() {
+    let source = "\
+static int a;
+int a;
+int b = 1;
+int b = 2;
+int c = b;
+int e();
+int e;
+int main() {
+    extern int f = 1;
+    static int s = b;
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("bad_globals", source, &[]),
+        [
+            "E0020 2:1 non-static declaration of `a` follows static declaration",
+            "E0003 4:1 redefinition of `b`",
+            "E0014 5:9 initializer element is not a compile-time constant",
+            "E0003 7:1 `e` redeclared as a different kind of symbol",
+            "E0021 9:20 `extern` variable `f` cannot have an initializer",
+            "E0014 10:20 initializer element is not a compile-time constant",
+        ]
+    );
+}
+
+#[test]
+fn pointer_misuse() {
+    let source = "\
+int main() {
+    int x = 1;
+    int *p = &x;
+    long *q = p;
+    &3;
+    p * 2;
+    *x;
+    p + p;
+    q - p;
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("pointer_misuse", source, &[]),
+        [
+            "W0006 4:15 incompatible pointer types",
+            "E0010 5:6 expression is not assignable",
+            "E0022 6:5 invalid operands to binary `*` (`int *` and `int`)",
+            "E0022 7:5 indirection requires pointer operand (`int` invalid)",
+            "E0022 8:5 invalid operands to binary `+` (`int *` and `int *`)",
+            "E0022 9:5 invalid operands to binary `-` (`long *` and `int *`)",
+        ]
+    );
+}
+
+#[test]
+fn array_misuse() {
+    let source = "\
+int main(void) {
+    int a[2] = {1, 2, 3};
+    int b[2];
+    int n = 3;
+    int c[0];
+    int d[-1];
+    int e[n];
+    a = b;
+    n[1];
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("array_misuse", source, &[]),
+        [
+            "E0021 2:23 excess elements in array initializer",
+            "E0024 5:11 array size must be positive",
+            "E0024 6:11 array size must be positive",
+            "E0014 7:11 array size is not an integer constant expression",
+            "E0010 8:5 array type `int[2]` is not assignable",
+            "E0022 9:5 subscripted value is not an array or pointer",
+        ]
+    );
+}
+
+#[test]
+fn multiple_declarators() {
+    let source = "\
+int g, g = 1, g = 2;
+int main(void) {
+    int x = 1, y[2] = {1, 2, 3}, x;
+    int *p = &x, q = p;
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("multiple_declarators", source, &[]),
+        [
+            "E0003 1:15 redefinition of `g`",
+            "E0021 3:30 excess elements in array initializer",
+            "E0003 3:34 `x` is already declared in this scope",
+            "E0023 4:22 incompatible pointer to integer conversion",
+        ]
+    );
+}
+
+#[test]
+fn member_misuse() {
+    let source = "\
+struct s { int a; };
+struct later;
+int main(void) {
+    struct s v;
+    struct later l;
+    v.b = 1;
+    v->a = 2;
+    int n = 0;
+    n.a = 3;
+    struct s w = {.c = 1};
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("member_misuse", source, &[]),
+        [
+            "E0025 5:5 variable `l` has incomplete type `struct later`",
+            "E0026 6:5 no member named `b` in `struct s`",
+            "E0022 7:5 member reference type `struct s` is not a pointer",
+            "E0022 9:5 member reference base type `int` is not a structure or union",
+            "E0026 10:19 no member named `c` in `struct s`",
+        ]
+    );
+}
+
+#[test]
+fn bad_enumerators() {
+    let source = "\
+int x;
+enum e { A, B = x, C = 2147483647, D, x };
+int A;
+int main(void) {
+    int B = 1;
+    enum { B };
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("bad_enumerators", source, &[]),
+        [
+            "E0014 2:17 enumerator value is not an integer constant expression",
+            "E0027 2:36 enumerator value 2147483648 is not representable in `int`",
+            "E0003 2:39 `x` is already declared in this scope",
+            "E0003 3:1 `A` is already declared in this scope",
+            "E0003 6:12 `B` is already declared in this scope",
+        ]
+    );
+}
+
+#[test]
+fn unspecified_parameters() {
+    let source = "\
+int printf();
+int f(void);
+int main(void) {
+    printf(\"%d\\n\", 1);
+    return f(1);
+}
+";
+    assert_eq!(
+        diagnose("unspecified_parameters", source, &["--std", "c17"]),
+        ["E0019 5:12 too many arguments to function `f`"]
+    );
+}
+
+#[test]
+fn conflicting_return_types() {
+    let source = "\
+int f();
+long f();
+int g(int a);
+int g();
+int main(void) { return 0; }
+";
+    assert_eq!(
+        diagnose("conflicting_return_types", source, &[]),
+        ["E0018 2:1 conflicting types for `f`"]
+    );
+}
+
+#[test]
+fn void_objects() {
+    let source = "\
+void a;
+extern void b;
+void f(void p);
+struct s { void m; };
+int main(void) {
+    void x;
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("void_objects", source, &[]),
+        [
+            "E0025 1:1 variable `a` has incomplete type `void`",
+            "E0025 3:13 parameter `p` has type `void`",
+            "E0025 4:17 field `m` has incomplete type `void`",
+            "E0025 6:5 variable `x` has incomplete type `void`",
+        ]
+    );
+}
+
+#[test]
+fn static_addresses() {
+    let source = "\
+int *end;
+int *back = end - 1;
+int main(void) {
+    int x = 1;
+    static int *q = &x;
+    static int *r = 1;
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("static_addresses", source, &[]),
+        [
+            "E0014 2:13 initializer element is not a compile-time constant",
+            "E0014 5:21 initializer element is not a compile-time constant",
+            "E0023 6:21 incompatible integer to pointer conversion",
+        ]
+    );
+}
+
+#[test]
+fn return_values() {
+    let source = "\
+void f(void) {
+    return 1;
+}
+int g(void) {
+    return;
+}
+void h(void) {
+    return;
+}
+int main(void) {
+    return f();
+}
+";
+    assert_eq!(
+        diagnose("return_values", source, &[]),
+        [
+            "E0028 2:12 void function `f` should not return a value",
+            "E0028 5:5 non-void function `g` should return a value",
+            "E0023 11:12 incompatible types",
+        ]
+    );
+}
+
+#[test]
+fn undefined_constant_operations() {
+    let source = "\
+enum { A = 1 << 32, B = (1L << 40 >> 40) + 1 };
+int b[B];
+int main(void) {
+    int x = 3;
+    unsigned char c = 1;
+    x = x % (2 - 2);
+    x = x >> -2;
+    x = c << 31;
+    x <<= 33;
+    switch (x) { case 1 / 0: break; }
+    return 1 / 0;
+}
+";
+    assert_eq!(
+        diagnose("undefined_constant_operations", source, &[]),
+        [
+            "W0008 1:17 shift count >= width of type `int`",
+            "E0014 1:12 enumerator value is not an integer constant expression",
+            "W0007 6:13 remainder by zero is undefined",
+            "W0008 7:14 shift count is negative",
+            "W0008 9:11 shift count >= width of type `int`",
+            "W0007 10:27 division by zero is undefined",
+            "E0014 10:23 case label does not reduce to an integer constant",
+            "W0007 11:16 division by zero is undefined",
+        ]
+    );
+}
+
+#[test]
+fn pointer_integer_comparisons() {
+    let source = "\
+int main(void) {
+    int x = 0;
+    int *p = &x;
+    long n = 5;
+    if (p == 5 || 0 != p || p == (void *)0 || p == 2 - 2) return 1;
+    if (n > p || p != x) return 2;
+    return 0;
+}
+";
+    assert_eq!(
+        diagnose("pointer_integer_comparisons", source, &[]),
+        [
+            "W0009 5:14 comparison between pointer and integer (`int *` and `int`)",
+            "W0009 6:9 comparison between pointer and integer (`long` and `int *`)",
+            "W0009 6:23 comparison between pointer and integer (`int *` and `int`)",
+        ]
+    );
+}
@@ -0,0 +1,85 @@
+// Links crust-compiled code with gcc-compiled code, calling back and forth,
+// to check that both agree on the System V AMD64 calling convention.
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// Compiles `<name>.c` with crust and `<name>_gcc.c` with gcc into one program
+// and runs it. The crust half's `main` returns 0 when every check passes, or
+// the number of the first one to fail.
+fn run(source: &Path) -> Result<(), String> {
+    let name = source.file_stem().unwrap().to_string_lossy();
+    let dir = env::temp_dir().join(format!("crust-interop-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let result = (|| {
+        // crust writes `crust_out.s` to the working directory, then tries to
+        // link it alone, which fails without the gcc half: only the assembly
+        // is used.
+        let crust = Command::new(env!("CARGO_BIN_EXE_crust"))
+            .arg(source)
+            .current_dir(&dir)
+            .output()
+            .expect("failed to run crust");
+        let asm = dir.join("crust_out.s");
+        if !asm.exists() {
+            return Err(format!(
+                "crust failed to compile {}.c:\n{}{}",
+                name,
+                String::from_utf8_lossy(&crust.stdout),
+                String::from_utf8_lossy(&crust.stderr)
+            ));
+        }
+
+        let program = dir.join(name.as_ref());
+        let gcc = Command::new("gcc")
+            .arg(&asm)
+            .arg(source.with_file_name(format!("{}_gcc.c", name)))
+            .arg("-o")
+            .arg(&program)
+            .output()
+            .expect("failed to run gcc");
+        if !gcc.status.success() {
+            return Err(format!(
+                "gcc failed to link {}:\n{}",
+                name,
+                String::from_utf8_lossy(&gcc.stderr)
+            ));
+        }
+
+        let status = Command::new(&program).status().unwrap();
+        match status.code() {
+            Some(0) => Ok(()),
+            code => Err(format!("check {:?} in {}.c failed", code, name)),
+        }
+    })();
+
+    fs::remove_dir_all(&dir).unwrap();
+    result
+}
+
+// Runs every crust half in tests/interop, reporting all the failures at once.
+#[test]
+fn interop() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/interop");
+    let mut sources: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "c")
+                && !path
+                    .file_stem()
+                    .unwrap()
+                    .to_string_lossy()
+                    .ends_with("_gcc")
+        })
+        .collect();
+    sources.sort();
+
+    let failures: Vec<String> = sources
+        .iter()
+        .filter_map(|source| run(source).err())
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,44 @@
+// Calls with more than six arguments in both directions, and the stack
+// alignment that gcc-compiled callees see.
+int gcc_weigh8(int a, int b, int c, int d, int e, int f, int g, int h);
+int gcc_last(int a, int b, int c, int d, int e, int f, int g, int h, int i, int j);
+int gcc_aligned(void);
+int gcc_aligned7(int a, int b, int c, int d, int e, int f, int g);
+int gcc_aligned8(int a, int b, int c, int d, int e, int f, int g, int h);
+int gcc_callbacks(void);
+
+int crust_weigh8(int a, int b, int c, int d, int e, int f, int g, int h) {
+    return a + 2 * b + 3 * c + 4 * d + 5 * e + 6 * f + 7 * g + 8 * h;
+}
+
+int crust_last(int a, int b, int c, int d, int e, int f, int g, int h, int i, int j) {
+    return j * 100 + i * 10 + h;
+}
+
+int main() {
+    if (gcc_weigh8(1, 2, 3, 4, 5, 6, 7, 8) != 204) return 1;
+    if (gcc_last(1, 2, 3, 4, 5, 6, 7, 8, 9, 10) != 1098) return 2;
+    if (gcc_weigh8(-1, 0, 0, 0, 0, 0, 0, -2) != -17) return 3;
+
+    // %rsp is 16-byte aligned at every call, whatever the number of stack
+    // arguments and temporaries pushed around the call.
+    if (!gcc_aligned()) return 4;
+    if (!gcc_aligned7(1, 2, 3, 4, 5, 6, 7)) return 5;
+    if (!gcc_aligned8(1, 2, 3, 4, 5, 6, 7, 8)) return 6;
+    if (1 + gcc_aligned() != 2) return 7;
+    if (2 * (1 + gcc_aligned7(1, 2, 3, 4, 5, 6, 7)) != 4) return 8;
+    int x = 1;
+    {
+        int y = 2;
+        if (!gcc_aligned8(x, y, 3, 4, 5, 6, 7, 7 + gcc_aligned7(1, 2, 3, 4, 5, 6, 7))) return 9;
+    }
+
+    // Arguments that are themselves calls with stack arguments.
+    if (gcc_weigh8(1, 1, 1, 1, 1, 1, 1, crust_weigh8(1, 1, 1, 1, 1, 1, 1, 1)) != 316) return 10;
+
+    // gcc calls back into crust with stack arguments.
+    int callbacks = gcc_callbacks();
+    if (callbacks)
+        return 20 + callbacks;
+    return 0;
+}
@@ -0,0 +1,35 @@
+/* The gcc-compiled half of the calls interop test. */
+int crust_weigh8(int a, int b, int c, int d, int e, int f, int g, int h);
+int crust_last(int a, int b, int c, int d, int e, int f, int g, int h, int i, int j);
+
+int gcc_weigh8(int a, int b, int c, int d, int e, int f, int g, int h) {
+    return a + 2 * b + 3 * c + 4 * d + 5 * e + 6 * f + 7 * g + 8 * h;
+}
+
+int gcc_last(int a, int b, int c, int d, int e, int f, int g, int h, int i, int j) {
+    return j * 100 + i * 10 + h;
+}
+
+/* The caller's %rsp was 16-byte aligned at the call exactly when this
+ * function's frame pointer is. */
+int gcc_aligned(void) {
+    return ((unsigned long)__builtin_frame_address(0) & 15) == 0;
+}
+
+int gcc_aligned7(int a, int b, int c, int d, int e, int f, int g) {
+    return ((unsigned long)__builtin_frame_address(0) & 15) == 0 && g == 7;
+}
+
+int gcc_aligned8(int a, int b, int c, int d, int e, int f, int g, int h) {
+    return ((unsigned long)__builtin_frame_address(0) & 15) == 0 && h == 8;
+}
+
+int gcc_callbacks(void) {
+    if (crust_weigh8(1, 2, 3, 4, 5, 6, 7, 8) != 204)
+        return 1;
+    if (crust_last(1, 2, 3, 4, 5, 6, 7, 8, 9, 10) != 1098)
+        return 2;
+    if (crust_weigh8(0, 0, 0, 0, 0, 0, -3, 1) != -13)
+        return 3;
+    return 0;
+}
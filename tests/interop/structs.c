@@ -0,0 +1,114 @@
+/* The crust-compiled half of the struct interop test, which calls into
+ * structs_gcc.c and back. Each type covers a different System V
+ * classification: one or two INTEGER eightbytes, or MEMORY. */
+struct tiny { char c; };
+struct pair { int a; int b; };
+struct trio { int a; int b; int c; };
+struct wide { long a; long b; };
+struct mix { char c; short s; int i; long l; };
+struct big { long a; long b; long c; };
+union word { long l; char bytes[12]; };
+
+long gcc_sum(struct pair p);
+struct tiny gcc_next(struct tiny t);
+struct trio gcc_trio(int a, int b, int c);
+struct mix gcc_mix(char c, short s, int i, long l);
+struct big gcc_scale(struct big b, int k);
+union word gcc_word(union word w);
+long gcc_spill(long a, long b, long c, long d, long e, struct wide w, struct pair p,
+               struct big big);
+int gcc_callbacks(void);
+
+struct pair crust_swap(struct pair p) {
+    struct pair swapped = {p.b, p.a};
+    return swapped;
+}
+
+struct tiny crust_next(struct tiny t) {
+    t.c++;
+    return t;
+}
+
+struct trio crust_rotate(struct trio t) {
+    struct trio rotated = {t.b, t.c, t.a};
+    return rotated;
+}
+
+struct mix crust_bump(struct mix m) {
+    m.c += 1;
+    m.s += 2;
+    m.i += 3;
+    m.l += 4;
+    return m;
+}
+
+struct big crust_big(long x) {
+    struct big b = {x, x + 1, x + 2};
+    return b;
+}
+
+union word crust_word(union word w) {
+    w.bytes[11] = 9;
+    return w;
+}
+
+long crust_spill(long a, long b, long c, long d, long e, struct wide w, struct pair p,
+                 struct big big) {
+    return a + b + c + d + e + w.a * 10 + w.b * 100 + p.a * 1000 + p.b * 10000 +
+           big.a * 100000 + big.b * 1000000 + big.c * 10000000;
+}
+
+/* Returns a nonzero code for the first mismatch. */
+int main(void) {
+    struct pair p = {3, 4};
+    if (gcc_sum(p) != 7)
+        return 1;
+    struct tiny t = {'x'};
+    if (gcc_next(t).c != 'y')
+        return 2;
+    struct trio trio = gcc_trio(1, 2, 3);
+    if (trio.a != 1 || trio.b != 2 || trio.c != 3)
+        return 3;
+    struct mix m = gcc_mix(-1, -2, -3, -4);
+    if (m.c != -1 || m.s != -2 || m.i != -3 || m.l != -4)
+        return 4;
+    struct big b = {1, 2, 3};
+    struct big scaled = gcc_scale(b, 3);
+    if (scaled.a != 3 || scaled.b != 6 || scaled.c != 9 || b.a != 1)
+        return 5;
+    if (gcc_scale(gcc_scale(b, 2), 5).c != 30)
+        return 6;
+    union word w;
+    w.l = 0;
+    w.bytes[10] = 1;
+    w = gcc_word(w);
+    if (w.bytes[10] != 1 || w.bytes[11] != 7)
+        return 7;
+    struct wide wide = {2, 3};
+    struct pair q = {4, 5};
+    struct big big = {6, 7, 8};
+    if (gcc_spill(1, 1, 1, 1, 1, wide, q, big) != 87654325)
+        return 8;
+    /* Struct results used directly, and in loops, must not leak stack. */
+    long total = 0;
+    for (int i = 0; i < 100000; i++)
+        total += gcc_trio(i, 1, 2).b + (i % 2 ? gcc_scale(b, 1) : crust_big(1)).c;
+    if (total != 400000)
+        return 9;
+    /* Struct results as operands, after the other operand is pushed. */
+    int r = 0;
+    r = 1 + gcc_trio(3, 4, 5).b;
+    if (r != 5)
+        return 10;
+    r = r + gcc_scale(b, 100).c;
+    if (r != 305)
+        return 11;
+    long l = 2;
+    l += gcc_mix(1, 2, 3, 4).l * gcc_scale(b, 2).b;
+    if (l != 18)
+        return 12;
+    int callbacks = gcc_callbacks();
+    if (callbacks)
+        return 20 + callbacks;
+    return 0;
+}
@@ -0,0 +1,87 @@
+/* The gcc-compiled half of the struct interop test. The types must match
+ * those in structs.c. */
+struct tiny { char c; };
+struct pair { int a; int b; };
+struct trio { int a; int b; int c; };
+struct wide { long a; long b; };
+struct mix { char c; short s; int i; long l; };
+struct big { long a; long b; long c; };
+union word { long l; char bytes[12]; };
+
+/* Defined by crust. */
+struct pair crust_swap(struct pair p);
+struct tiny crust_next(struct tiny t);
+struct trio crust_rotate(struct trio t);
+struct mix crust_bump(struct mix m);
+struct big crust_big(long x);
+union word crust_word(union word w);
+long crust_spill(long a, long b, long c, long d, long e, struct wide w, struct pair p,
+                 struct big big);
+
+long gcc_sum(struct pair p) { return p.a + p.b; }
+
+struct tiny gcc_next(struct tiny t) {
+    t.c++;
+    return t;
+}
+
+struct trio gcc_trio(int a, int b, int c) {
+    struct trio t = {a, b, c};
+    return t;
+}
+
+struct mix gcc_mix(char c, short s, int i, long l) {
+    struct mix m = {c, s, i, l};
+    return m;
+}
+
+struct big gcc_scale(struct big b, int k) {
+    b.a *= k;
+    b.b *= k;
+    b.c *= k;
+    return b;
+}
+
+union word gcc_word(union word w) {
+    w.bytes[11] = 7;
+    return w;
+}
+
+/* `w` no longer fits in the one register left, so goes on the stack while
+ * `p` still takes the last register. */
+long gcc_spill(long a, long b, long c, long d, long e, struct wide w, struct pair p,
+               struct big big) {
+    return a + b + c + d + e + w.a * 10 + w.b * 100 + p.a * 1000 + p.b * 10000 +
+           big.a * 100000 + big.b * 1000000 + big.c * 10000000;
+}
+
+/* Calls back into crust, returning a nonzero code for the first mismatch. */
+int gcc_callbacks(void) {
+    struct pair p = {1, 2};
+    struct pair swapped = crust_swap(p);
+    if (swapped.a != 2 || swapped.b != 1)
+        return 1;
+    struct tiny t = {'a'};
+    if (crust_next(t).c != 'b')
+        return 2;
+    struct trio r = crust_rotate((struct trio){1, 2, 3});
+    if (r.a != 2 || r.b != 3 || r.c != 1)
+        return 3;
+    struct mix m = crust_bump((struct mix){1, 2, 3, 4});
+    if (m.c != 2 || m.s != 4 || m.i != 6 || m.l != 8)
+        return 4;
+    struct big b = crust_big(5);
+    if (b.a != 5 || b.b != 6 || b.c != 7)
+        return 5;
+    union word w = {0};
+    w.bytes[10] = 3;
+    w = crust_word(w);
+    if (w.bytes[10] != 3 || w.bytes[11] != 9)
+        return 6;
+    struct wide wide = {2, 3};
+    struct pair q = {4, 5};
+    struct big big = {6, 7, 8};
+    if (crust_spill(1, 1, 1, 1, 1, wide, q, big) != 87654325)
+        return 7;
+    return 0;
+}
@@ -0,0 +1,61 @@
+// Compiles every program in tests/programs with crust and runs it.
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// Compiles `source` with crust, which links it with gcc, and runs the result.
+// `main` returns 0 when every check passes, or the number of the first one to
+// fail.
+fn run(source: &Path) -> Result<(), String> {
+    let name = source.file_stem().unwrap().to_string_lossy();
+    let dir = env::temp_dir().join(format!("crust-programs-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let result = (|| {
+        let crust = Command::new(env!("CARGO_BIN_EXE_crust"))
+            .arg(source)
+            .current_dir(&dir)
+            .output()
+            .expect("failed to run crust");
+        if !crust.status.success() {
+            return Err(format!(
+                "crust failed to compile {}.c:\n{}{}",
+                name,
+                String::from_utf8_lossy(&crust.stdout),
+                String::from_utf8_lossy(&crust.stderr)
+            ));
+        }
+
+        // Capture the program's output so it doesn't interleave with the test
+        // harness's.
+        let output = Command::new(dir.join("crust_out"))
+            .output()
+            .map_err(|err| format!("failed to run {}.c: {}", name, err))?;
+        match output.status.code() {
+            Some(0) => Ok(()),
+            code => Err(format!("check {:?} in {}.c failed", code, name)),
+        }
+    })();
+
+    fs::remove_dir_all(&dir).unwrap();
+    result
+}
+
+// Runs every program, reporting all the failures at once.
+#[test]
+fn programs() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let mut sources: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "c"))
+        .collect();
+    sources.sort();
+
+    let failures: Vec<String> = sources
+        .iter()
+        .filter_map(|source| run(source).err())
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,31 @@
+// Pointers with static storage duration initialized with address constants.
+int g = 5;
+int *gp = &g;
+char *msg = "hi";
+int arr[4] = {1, 2, 3, 4};
+int *third = &arr[2];
+int *end = arr + 4;
+struct pt { int x; int y; } p = {7, 8};
+int *py = &p.y;
+struct pt *pp = &p;
+char *tail = "hello" + 3;
+void *vp = (void *)&g;
+char c[2][3] = {"ab", "cd"};
+char *c1 = c[1];
+int *zero = 0;
+int main(void) {
+    static int x = 3;
+    static int *q = &x;
+    if (*gp != 5) return 1;
+    if (msg[1] != 'i') return 2;
+    if (*third != 3) return 3;
+    if (*py != 8) return 5;
+    if (pp->x != 7) return 6;
+    if (*q != 3) return 7;
+    if (*tail != 'l') return 8;
+    if (*(int *)vp != 5) return 9;
+    if (c1[1] != 'd') return 10;
+    if (zero) return 11;
+    if (end - arr != 4) return 12;
+    return 0;
+}
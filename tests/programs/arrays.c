@@ -0,0 +1,55 @@
+// Arrays: subscripting, multi-dimensional arrays, decay to pointers,
+// sizeof, and brace initializers that zero-fill the rest.
+int g[5] = {1, 2};
+long grid[2][3] = {{1, 2, 3}, {4, 5, 6}};
+int counts[4];
+
+int sum(int *a, int n) {
+    int total = 0;
+    for (int i = 0; i < n; i++)
+        total += a[i];
+    return total;
+}
+
+int row_sum(long row[][3], int r) {
+    return row[r][0] + row[r][1] + row[r][2];
+}
+
+int main(void) {
+    int a[3] = {1, 2, 3};
+    if (a[0] != 1 || a[2] != 3) return 1;
+    a[1] = 20;
+    if (a[1] != 20 || *(a + 1) != 20 || 1[a] != 20) return 2;
+
+    if (sizeof a != 12 || sizeof a[0] != 4) return 3;
+    if (sizeof grid != 48 || sizeof grid[1] != 24) return 4;
+    if (sum(a, 3) != 24) return 5;
+
+    if (g[0] != 1 || g[1] != 2 || g[2] != 0 || g[4] != 0) return 6;
+    if (counts[3] != 0) return 7;
+    int z[4] = {7};
+    if (z[0] != 7 || z[1] != 0 || z[3] != 0) return 8;
+
+    if (grid[1][2] != 6 || grid[0][1] != 2) return 9;
+    grid[1][0] = 40;
+    if (row_sum(grid, 1) != 51) return 10;
+
+    int m[3][4];
+    for (int i = 0; i < 3; i++)
+        for (int j = 0; j < 4; j++)
+            m[i][j] = i * 10 + j;
+    if (m[2][3] != 23 || &m[1][0] - &m[0][0] != 4) return 11;
+    int *flat = m[0];
+    if (flat[6] != 12) return 12;
+
+    int *p = a;
+    p++;
+    if (*p != 20 || p - a != 1) return 13;
+
+    int b[] = {5, 6, 7, 8};
+    if (sizeof b / sizeof b[0] != 4) return 14;
+
+    char s[8] = "hi";
+    if (s[1] != 'i' || s[2] != 0 || s[7] != 0) return 15;
+    return 0;
+}
@@ -0,0 +1,46 @@
+// Block scopes: inner declarations shadow outer ones, and the stack slots of
+// a finished block are reused without disturbing live variables.
+int main() {
+    int a = 1;
+    int b = 2;
+    {
+        int a = 10;
+        if (a != 10 || b != 2) return 1;
+        b = a + b;
+        {
+            int b = 100;
+            a = a + b;
+            if (a != 110) return 2;
+        }
+        if (a != 110 || b != 12) return 3;
+        a = 20;
+    }
+    if (a != 1 || b != 12) return 4;
+
+    // Declared after the block above ended, so it takes one of its slots.
+    int c = 3;
+    {
+        int d = 4;
+        int e = 5;
+        c = c + d + e;
+    }
+    {
+        int f = 6;
+        int g = 7;
+        if (f != 6 || g != 7 || c != 12) return 5;
+    }
+    int h = 8;
+    if (a != 1 || b != 12 || c != 12 || h != 8) return 6;
+
+    {
+        int a = 30;
+        {
+            int a = 40;
+            if (a != 40) return 7;
+        }
+        if (a != 30) return 8;
+    }
+    {}
+    if (a != 1) return 9;
+    return 0;
+}
@@ -0,0 +1,59 @@
+// Structs passed, returned and assigned by value between crust functions
+// are copies, whether they travel in registers or in memory.
+struct small { char c; int i; };
+struct wide { long a; long b; };
+struct big { long a; long b; long c; int tail[3]; };
+
+struct small make_small(char c, int i) {
+    struct small s = {c, i};
+    return s;
+}
+
+struct wide flip(struct wide w) {
+    long t = w.a;
+    w.a = w.b;
+    w.b = t;
+    return w;
+}
+
+struct big grow(struct big b, int k) {
+    b.a *= k;
+    b.b *= k;
+    b.c *= k;
+    b.tail[2] += k;
+    return b;
+}
+
+// Struct arguments after the integer registers are used up.
+long late(long a, long b, long c, long d, long e, long f, struct wide w, struct small s,
+          struct big big) {
+    return a + b + c + d + e + f + w.a * 10 + w.b * 100 + s.i * 1000 + big.tail[2] * 10000;
+}
+
+int main(void) {
+    struct small s = make_small('s', 7);
+    if (s.c != 's' || s.i != 7) return 1;
+    if (make_small('t', 8).i != 8) return 2;
+
+    struct wide w = {1, 2};
+    struct wide f = flip(w);
+    if (w.a != 1 || w.b != 2 || f.a != 2 || f.b != 1) return 3;
+
+    struct big b = {1, 2, 3, {4, 5, 6}};
+    struct big g = grow(b, 3);
+    if (b.a != 1 || b.tail[2] != 6) return 4;
+    if (g.a != 3 || g.b != 6 || g.c != 9 || g.tail[0] != 4 || g.tail[2] != 9) return 5;
+
+    // Assignment copies every member, and later changes do not alias.
+    struct big copy;
+    copy = g;
+    g.tail[1] = 50;
+    if (copy.tail[1] != 5 || copy.c != 9) return 6;
+    struct wide chained;
+    chained = w = f;
+    if (chained.a != 2 || w.a != 2) return 7;
+
+    if (late(1, 1, 1, 1, 1, 1, w, s, g) != 6 + 20 + 100 + 7000 + 90000) return 8;
+    if (grow(grow(b, 2), 5).tail[2] != 13) return 9;
+    return 0;
+}
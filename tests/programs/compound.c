@@ -0,0 +1,80 @@
+// Compound assignment operators update the variable and yield its new value.
+int main() {
+    int a = 7;
+    a += 5;
+    if (a != 12) return 1;
+    a -= 20;
+    if (a != -8) return 2;
+    a *= -3;
+    if (a != 24) return 3;
+    a /= 5;
+    if (a != 4) return 4;
+    a = -17;
+    a %= 5;
+    if (a != -2) return 5;
+    a = 3;
+    a <<= 4;
+    if (a != 48) return 6;
+    a = -64;
+    a >>= 3;
+    if (a != -8) return 7;
+    a = 12;
+    a &= 10;
+    if (a != 8) return 8;
+    a |= 3;
+    if (a != 11) return 9;
+    a ^= 6;
+    if (a != 13) return 10;
+
+    // The value of the expression is the assigned value, and assignment
+    // groups from the right.
+    int b = 2;
+    int c = 3;
+    if ((a += 1) != 14) return 11;
+    a = 1;
+    a += b *= c -= 1;
+    if (a != 5 || b != 4 || c != 2) return 12;
+    // The right-hand side is evaluated in full before the update.
+    a = 10;
+    a -= 2 + 3;
+    if (a != 5) return 13;
+    a *= a + 1;
+    if (a != 30) return 14;
+
+    // Unsigned operands divide and shift logically, and narrow operands are
+    // promoted before the operation and converted back afterwards.
+    unsigned u = 0x80000000u;
+    u >>= 4;
+    if (u != 0x08000000u) return 15;
+    u <<= 5;
+    if (u != 0) return 16;
+    u = 4294967295u;
+    u /= 2;
+    if (u != 2147483647) return 17;
+    u = 7;
+    u -= 9;
+    if (u != 4294967294u) return 18;
+    u %= 10;
+    if (u != 4) return 19;
+    unsigned char uc = 250;
+    uc += 10;
+    if (uc != 4) return 20;
+    uc = 0xf0;
+    uc >>= 4;
+    if (uc != 15) return 21;
+    uc <<= 5;
+    if (uc != 0xe0) return 22;
+    signed char sc = -128;
+    sc >>= 1;
+    if (sc != -64) return 23;
+    short sh = 0x4000;
+    sh *= 4;
+    if (sh != 0) return 24;
+    long l = 1;
+    l <<= 40;
+    if (l != 1099511627776) return 25;
+    unsigned long ul = 1;
+    ul -= 2;
+    if (ul >> 63 != 1) return 26;
+    return 0;
+}
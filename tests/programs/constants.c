@@ -0,0 +1,22 @@
+// Integer constant expressions follow the usual arithmetic conversions.
+enum { K = (0u-1)>>28, L = -1 < 0u, M = (unsigned char)255 + 1, N = 0 ? 1 : -1 };
+int a[(1u-2)>0?3:1];
+long b[sizeof(int) - 5 < 0 ? 1 : 2];
+int pick(unsigned x) {
+    switch (x) {
+    case -1u/2: return 1;
+    case -1/2: return 2;
+    default: return 3;
+    }
+}
+int main(void) {
+    if (K != 15) return 1;
+    if (L != 0) return 2;
+    if (M != 256) return 3;
+    if (sizeof a != 12) return 4;
+    if (sizeof b != 16) return 5;
+    if (pick(2147483647u) != 1) return 6;
+    if (pick(0) != 2) return 7;
+    if (N != -1) return 8;
+    return 0;
+}
@@ -0,0 +1,39 @@
+// Declarations with several declarators, which share the specifiers and
+// storage class but not the `*`s or array sizes.
+int gi = 4, gj, *gp = &gi, ga[3] = {1, 2, 3};
+static int hidden = 5, also_hidden;
+struct point { int x, y; } origin, unit = {1, 1}, *where = &unit;
+
+int count(void) {
+    static int calls, step = 2;
+    calls += step;
+    return calls;
+}
+
+int main(void) {
+    int i, j;
+    i = 1;
+    j = 2;
+    if (i != 1 || j != 2) return 1;
+
+    // Each declarator is in scope from its own end on.
+    int a[3] = {7, 8, 9}, *p = a + 1, n = *p + 1;
+    if (sizeof a != 12 || sizeof p != 8 || *p != 8 || n != 9) return 2;
+
+    if (gi != 4 || gj != 0 || *gp != 4 || ga[2] != 3) return 3;
+    if (hidden != 5 || also_hidden != 0) return 4;
+    if (origin.x != 0 || where->y != 1 || sizeof unit != 8) return 5;
+
+    int sum = 0;
+    for (int k = 0, limit = 4; k < limit; k++)
+        sum += k;
+    if (sum != 6) return 6;
+
+    count();
+    if (count() != 4) return 7;
+
+    // One struct definition, shared by both declarators.
+    struct { char c; int v; } s = {'a', 1}, t = s;
+    if (t.c != 'a' || t.v != 1) return 8;
+    return 0;
+}
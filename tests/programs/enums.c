@@ -0,0 +1,46 @@
+// Enumerators: implicit and explicit values, use in constant expressions,
+// and scoping in the ordinary identifier namespace.
+enum color { RED, GREEN, BLUE };
+enum code { OK = 0, WARN = 10, FAIL, FATAL = FAIL * 2, LAST = -1, AFTER };
+enum { SIZE = 4 };
+
+int table[SIZE];
+
+int name(enum color c) {
+    switch (c) {
+    case RED: return 1;
+    case GREEN: return 2;
+    case BLUE: return 3;
+    }
+    return 0;
+}
+
+int main(void) {
+    if (RED != 0 || GREEN != 1 || BLUE != 2) return 1;
+    if (WARN != 10 || FAIL != 11 || FATAL != 22 || AFTER != 0) return 2;
+    if (sizeof table != 16) return 3;
+    if (name(BLUE) != 3 || name(GREEN) != 2) return 4;
+
+    enum color c = GREEN;
+    c++;
+    if (c != BLUE || sizeof c != sizeof(int)) return 5;
+    int i = RED + BLUE * 10;
+    if (i != 20) return 6;
+
+    // An enumerator shadows an outer variable, and an inner variable can
+    // shadow an enumerator.
+    int RED_ish = 5;
+    {
+        enum { RED_ish = 7 };
+        if (RED_ish != 7) return 7;
+        int GREEN = 9;
+        if (GREEN != 9) return 8;
+    }
+    if (RED_ish != 5 || GREEN != 1) return 9;
+
+    // The enum tag and its constants are usable after a local declaration.
+    enum local { A = 3, B };
+    enum local l = B;
+    if (l != 4) return 10;
+    return 0;
+}
@@ -0,0 +1,23 @@
+// Character constants and string literals decode every escape sequence, and
+// adjacent string literals are joined into one.
+int puts(char *s);
+int printf();
+int strcmp(char *a, char *b);
+int main(void) {
+    if ('a' != 97 || '\n' != 10 || '\0' != 0) return 1;
+    if ('\x41' != 'A' || '\101' != 'A' || '\177' != 127) return 2;
+    if ('\'' != 39 || '"' != 34 || '\\' != 92) return 3;
+    if ('\xff' != -1) return 4;
+    if (sizeof 'a' != sizeof(int)) return 5;
+    char *s = "ab" "c" "\x41\n";
+    if (strcmp(s, "abcA\n") != 0) return 6;
+    if (sizeof("ab" "cd") != 5) return 7;
+    char *t = "nul\0after";
+    if (t[3] != 0 || t[4] != 'a') return 8;
+    char *u = "one";
+    char *v = "two";
+    if (u == v || strcmp(u, "one") != 0 || strcmp(v, "two") != 0) return 9;
+    if (puts("hello\tworld") < 0) return 10;
+    if (printf("%s=%d%c", "answer", 42, '\n') != 10) return 11;
+    return 0;
+}
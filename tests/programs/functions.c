@@ -0,0 +1,88 @@
+// Several functions calling each other, each with its own frame, locals and
+// labels.
+int four(void);
+int is_odd(int n);
+
+int three() {
+    int x = 3;
+    goto out;
+    x = 0;
+out:
+    return x;
+}
+
+// Calls a function defined further down, through its prototype.
+int seven() {
+    int a = three();
+    return a + four();
+}
+
+int four() {
+    int x = 1;
+    {
+        int y = three();
+        x += y;
+    }
+    return x;
+}
+
+int add(int a, int b) {
+    return a + b;
+}
+
+int factorial(int n) {
+    if (n <= 1)
+        return 1;
+    return n * factorial(n - 1);
+}
+
+int is_even(int n) {
+    if (n == 0)
+        return 1;
+    return is_odd(n - 1);
+}
+
+int is_odd(int n) {
+    if (n == 0)
+        return 0;
+    return is_even(n - 1);
+}
+
+// Parameters are ordinary locals.
+int count_down(int n) {
+    int steps = 0;
+    while (n > 0) {
+        n--;
+        steps++;
+    }
+    return steps * 10 + n;
+}
+
+// Falls off the end.
+int nothing() {
+    int unused = 5;
+    if (unused)
+        goto out;
+out:
+    unused = 0;
+}
+
+int main() {
+    int before = 11;
+    if (three() != 3) return 1;
+    if (four() != 4) return 2;
+    if (seven() != 7) return 3;
+    if (seven() * three() - four() != 17) return 4;
+    if (add(add(1, 2), add(3, 4)) != 10) return 5;
+    if (add(10, -3) != 7) return 6;
+    if (factorial(5) != 120) return 7;
+    if (!is_even(10) || is_even(7) || !is_odd(7)) return 8;
+    if (count_down(4) != 40) return 9;
+    nothing();
+    if (before != 11) return 10;
+    int sum = 0;
+    for (int i = 0; i < 3; i++)
+        sum += add(seven(), i);
+    if (sum != 24) return 11;
+    return 0;
+}
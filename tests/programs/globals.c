@@ -0,0 +1,52 @@
+// File-scope variables with external and internal linkage, tentative
+// definitions, extern declarations and static locals.
+int initialized = 7;
+int zeroed;
+int tentative;
+int tentative = 3;
+static int hidden = 2 * 3 + 1;
+long wide = 1L << 40;
+extern int later;
+
+int counter() {
+    static int calls;
+    calls = calls + 1;
+    return calls;
+}
+
+int bump() {
+    extern int initialized;
+    initialized += 10;
+    return initialized;
+}
+
+int shadow() {
+    int zeroed = 5;
+    {
+        extern int zeroed;
+        zeroed = 9;
+    }
+    return zeroed;
+}
+
+int main() {
+    if (initialized != 7) return 1;
+    if (zeroed != 0) return 2;
+    if (tentative != 3) return 3;
+    if (hidden != 7) return 4;
+    if (wide != 1099511627776) return 5;
+    if (later != 42) return 6;
+    later = 1;
+    if (later != 1) return 7;
+    if (counter() != 1 || counter() != 2 || counter() != 3) return 8;
+    if (bump() != 17 || initialized != 17) return 9;
+    if (shadow() != 5 || zeroed != 9) return 10;
+    {
+        int initialized = 1;
+        if (initialized != 1) return 11;
+    }
+    if (initialized != 17) return 12;
+    return 0;
+}
+
+int later = 42;
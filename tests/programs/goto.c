@@ -0,0 +1,53 @@
+// goto jumps forward and backward, into and out of nested blocks.
+int main() {
+    int r = 0;
+    goto skip;
+    r = 100;
+skip:
+    if (r != 0) return 1;
+
+    // A backward jump makes a loop.
+    int i = 0;
+    int sum = 0;
+again:
+    sum += i;
+    i++;
+    if (i < 5) goto again;
+    if (sum != 10) return 2;
+
+    // Jumping past a declaration into a block and back out of it.
+    r = 0;
+    goto inside;
+    {
+        int unused = 5;
+        r = unused;
+    inside:
+        r = r + 1;
+        {
+            int inner = r * 2;
+            if (inner == 2) goto out;
+            r = inner;
+        }
+    }
+out:
+    if (r != 1) return 3;
+
+    // Leaving a block with a goto leaves the stack as it was.
+    int before = 7;
+    for (int n = 0; n < 3; n++) {
+        int a = n;
+        {
+            int b = a + 1;
+            if (b == 2) goto done;
+        }
+    }
+    return 4;
+done:
+    if (before != 7) return 5;
+    int after = 9;
+    if (before + after != 16) return 6;
+    goto end;
+    return 7;
+end:
+    return 0;
+}
@@ -0,0 +1,28 @@
+// Prefix operators yield the updated value, postfix ones the old value.
+int main() {
+    int a = 5;
+    int b = ++a;
+    if (a != 6 || b != 6) return 1;
+    b = a++;
+    if (a != 7 || b != 6) return 2;
+    b = --a;
+    if (a != 6 || b != 6) return 3;
+    b = a--;
+    if (a != 5 || b != 6) return 4;
+
+    // `a+++b` is `a++ + b`.
+    b = 10;
+    int c = a+++b;
+    if (c != 15 || a != 6 || b != 10) return 5;
+    c = a---b;
+    if (c != -4 || a != 5) return 6;
+
+    c = ++a * 2;
+    if (c != 12 || a != 6) return 7;
+    c = -a--;
+    if (c != -6 || a != 5) return 8;
+    if (!a-- != 0 || a != 4) return 9;
+    a = 0;
+    if (a++ || a != 1) return 10;
+    return 0;
+}
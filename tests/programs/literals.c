@@ -0,0 +1,25 @@
+// Integer constants keep their value and the type C11 6.4.4.1 gives them.
+int main() {
+    if (0x1F != 31) return 1;
+    if (0755 != 493) return 2;
+    if (0b1010 != 10) return 3;
+    // Decimal constants that do not fit in int become long, while octal and
+    // hexadecimal ones try the unsigned types first.
+    if (-2147483648 > 0) return 4;
+    if (2147483648 - 2147483649 != -1) return 5;
+    if (0xffffffff < 0) return 6;
+    if (0xffffffff + 1 != 0) return 7;
+    if (037777777777 + 1 != 0) return 8;
+    if (-1 < 0u) return 9;
+    if (-1 < 0ul || -1 > 0l) return 10;
+    if (-1 >= 0x7fffffff) return 11;
+    long big = 4294967296;
+    if (big >> 32 != 1) return 12;
+    if (10u / 3 != 3 || 0xffffffffu + 1 != 0) return 13;
+    if (0x7fffffffffffffff + 0 < 0) return 14;
+    if (0x8000000000000000 >> 63 != 1) return 15;
+    if (1ull << 63 >> 63 != 1) return 16;
+    if (sizeof 1 != 4 || sizeof 1L != 8 || sizeof 1u != 4 || sizeof 1ull != 8) return 17;
+    if (sizeof 2147483648 != 8 || sizeof 0xffffffff != 4 || sizeof 'a' != 4) return 18;
+    return 0;
+}
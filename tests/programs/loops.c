@@ -0,0 +1,62 @@
+// while, do-while and for loops with break and continue.
+int main() {
+    int i = 0;
+    int sum = 0;
+    while (i < 5) {
+        sum += i;
+        i++;
+    }
+    if (sum != 10 || i != 5) return 1;
+
+    // The body of a do-while runs at least once.
+    i = 10;
+    do
+        i++;
+    while (i < 5);
+    if (i != 11) return 2;
+
+    sum = 0;
+    for (int j = 0; j < 10; j++) {
+        if (j % 2)
+            continue;
+        if (j == 8)
+            break;
+        sum += j;
+    }
+    if (sum != 12) return 3;
+
+    // `continue` in a for loop still runs the increment; in a do-while it
+    // goes to the condition.
+    sum = 0;
+    i = 0;
+    do {
+        i++;
+        if (i < 3)
+            continue;
+        sum += i;
+    } while (i < 5);
+    if (sum != 12) return 4;
+
+    // break and continue apply to the innermost loop.
+    sum = 0;
+    for (int a = 0; a < 3; a++)
+        for (int b = 0; b < 3; b++) {
+            if (b == a)
+                continue;
+            if (b > a)
+                break;
+            sum += 10 * a + b;
+        }
+    if (sum != 10 + 20 + 21) return 5;
+
+    // A for loop with every clause empty, and a shadowing loop variable.
+    int k = 0;
+    for (;;) {
+        if (++k == 4)
+            break;
+    }
+    for (int k = 0; k < 100; k++) {
+    }
+    if (k != 4) return 6;
+    return 0;
+}
@@ -0,0 +1,19 @@
+// A lone `;` is an empty statement, wherever a statement may go.
+int main(void) {
+    int i;
+    int n = 0;
+    for (i = 0; i < 10; i++) ;
+    if (i != 10) return 1;
+    while (n++ < 5) ;
+    if (n != 6) return 2;
+    ;;
+    switch (i) {
+    case 10: ;
+    default: ;
+    }
+    if (i) ; else return 3;
+    goto out;
+    return 4;
+out: ;
+    return 0;
+}
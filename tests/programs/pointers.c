@@ -0,0 +1,67 @@
+// Pointers: address-of, dereference, arithmetic scaled by the pointee size,
+// differences, comparisons and null pointer constants.
+int set(int *p, int value) {
+    *p = value;
+    return value;
+}
+
+long *pick(long *a, long *b, int first) {
+    return first ? a : b;
+}
+
+int main() {
+    int x = 1;
+    int *p = &x;
+    *p = 5;
+    if (x != 5 || *p != 5) return 1;
+    set(&x, 7);
+    if (x != 7) return 2;
+
+    int **pp = &p;
+    **pp = 9;
+    if (x != 9 || *pp != &x) return 3;
+
+    long l0 = 10;
+    long l1 = 11;
+    long *q = &l0;
+    if (*q != 10) return 4;
+    if ((char *)(q + 1) - (char *)q != 8) return 5;
+    if ((q + 3) - q != 3 || q - (q + 3) != -3) return 6;
+    if ((long)(q + 2) - (long)q != 16) return 7;
+    if (sizeof((q + 3) - q) != 8 || sizeof p != 8 || sizeof *q != 8) return 18;
+
+    long *r = q;
+    r++;
+    r += 2;
+    --r;
+    if (r - q != 2) return 8;
+    if (!(q < r) || q >= r || r <= q || !(r != q)) return 9;
+    if (pick(q, r, 0) != r || pick(&l1, q, 1) != &l1) return 10;
+
+    int *null = 0;
+    if (null) return 11;
+    if (null != 0 || !(p != 0)) return 12;
+    p = (int *)0;
+    if (p != null) return 13;
+
+    char c = 'x';
+    char *s = &c;
+    *s = 'a';
+    (*s)++;
+    if (c != 'b') return 14;
+    short h = -3;
+    short *hp = &h;
+    *hp *= 2;
+    if (h != -6) return 15;
+    unsigned char u = 250;
+    unsigned char *up = &u;
+    *up += 10;
+    if (u != 4) return 16;
+
+    // Pointers compare as unsigned 64-bit values.
+    unsigned long high = 0x8000000000000000;
+    char *big = (char *)high;
+    char *low = (char *)1;
+    if (!(big > low)) return 17;
+    return 0;
+}
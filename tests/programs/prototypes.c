@@ -0,0 +1,16 @@
+// `()` leaves the parameters unspecified; `(void)` says there are none.
+int printf();
+int twice();
+int twice(int x) { return 2 * x; }
+int none() { return 7; }
+int sum();
+
+int main(void) {
+    printf("%d %s\n", 1, "two");
+    if (twice(21) != 42) return 1;
+    if (none() != 7) return 2;
+    if (sum(1, 2, 3) != 6) return 3;
+    return 0;
+}
+
+int sum(int a, int b, int c) { return a + b + c; }
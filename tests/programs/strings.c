@@ -0,0 +1,14 @@
+// String literals are arrays of char that decay to pointers.
+int main(void) {
+    char *p = "hello";
+    char c = "abc"[1];
+    if (sizeof "abc" != 4) return 1;
+    if (sizeof("") != 1) return 2;
+    if (c != 'b') return 3;
+    if (p[4] != 'o') return 4;
+    if (*"xyz" != 'x') return 5;
+    if (*("xyz" + 2) != 'z') return 6;
+    char a[] = "hi";
+    if (sizeof a != 3) return 7;
+    return 0;
+}
@@ -0,0 +1,108 @@
+// Structs and unions: member layout and padding, sizeof, `.` and `->`,
+// anonymous members, nested aggregates and designated initializers.
+struct pair {
+    char c;
+    int i;
+};
+
+struct mixed {
+    char a;
+    long b;
+    short c;
+    char d;
+};
+
+struct node {
+    int value;
+    struct node *next;
+};
+
+union word {
+    int i;
+    unsigned char bytes[4];
+    short halves[2];
+};
+
+struct tagged {
+    int kind;
+    union {
+        int n;
+        long l;
+    };
+    struct {
+        char x;
+        char y;
+    };
+};
+
+struct outer {
+    char tag;
+    struct pair inner;
+    int arr[3];
+};
+
+struct point {
+    int x;
+    int y;
+};
+
+struct point origin = {.y = 4};
+
+int sum_list(struct node *n) {
+    int total = 0;
+    for (; n; n = n->next)
+        total += n->value;
+    return total;
+}
+
+int main(void) {
+    struct pair p;
+    if (sizeof p != 8 || sizeof(struct pair) != 8) return 1;
+    if ((char *)&p.i - (char *)&p != 4) return 2;
+    struct mixed m;
+    if (sizeof m != 24) return 3;
+    if ((char *)&m.b - (char *)&m != 8 || (char *)&m.c - (char *)&m != 16) return 4;
+    if ((char *)&m.d - (char *)&m != 18) return 5;
+
+    p.c = 'a';
+    p.i = 100;
+    struct pair *pp = &p;
+    pp->i += 5;
+    if (p.c != 'a' || p.i != 105 || (*pp).i != 105) return 6;
+
+    struct node c = {3, 0};
+    struct node b = {2, &c};
+    struct node a = {1, &b};
+    if (sum_list(&a) != 6 || a.next->next->value != 3) return 7;
+
+    union word w;
+    if (sizeof w != 4) return 8;
+    w.i = 0x01020304;
+    if (w.bytes[0] != 4 || w.bytes[3] != 1 || w.halves[1] != 0x0102) return 9;
+    if ((char *)&w.halves[0] != (char *)&w) return 10;
+
+    struct tagged t;
+    if (sizeof t != 24) return 11;
+    if ((char *)&t.l - (char *)&t != 8 || (char *)&t.y - (char *)&t != 17) return 12;
+    t.l = 0;
+    t.n = 7;
+    t.x = 'x';
+    t.y = 'y';
+    if (t.l != 7 || t.x != 'x' || t.y != 'y') return 13;
+
+    struct outer o = {'o', {'p', 9}, {1, 2, 3}};
+    if (sizeof o != 24) return 14;
+    if ((char *)&o.inner - (char *)&o != 4 || (char *)&o.arr - (char *)&o != 12) return 15;
+    if (o.inner.i != 9 || o.arr[2] != 3) return 16;
+    struct outer *op = &o;
+    op->inner.c = 'q';
+    op->arr[1] = 20;
+    if (o.inner.c != 'q' || o.arr[1] != 20) return 17;
+
+    struct point q = {.y = 2, .x = 1};
+    if (q.x != 1 || q.y != 2) return 18;
+    if (origin.x != 0 || origin.y != 4) return 19;
+    struct point pts[2] = {{1, 2}, [1].y = 5};
+    if (pts[1].x != 0 || pts[1].y != 5 || sizeof pts != 16) return 20;
+    return 0;
+}
@@ -0,0 +1,139 @@
+// Switch statements: labels in any order, fall-through, jump tables for
+// dense cases, and switches nested in loops and other switches.
+
+// A declaration between cases: jumping to the second case must still leave
+// the stack pointer below `y`, so the call to `clobber` cannot overwrite it.
+int clobber(int a, int b) {
+    int pad = a * b;
+    return pad;
+}
+
+int declared(int x) {
+    int r = 0;
+    switch (x) {
+    case 1:
+        r = 1;
+        int y;
+    case 2:
+        y = 5;
+        r = 10 + y;
+        clobber(7, 9);
+        r += y;
+        break;
+    }
+    return r;
+}
+
+int classify(int x) {
+    switch (x) {
+    case 0:
+        return 10;
+    default:
+        return 30;
+    case 7:
+        return 70;
+    }
+}
+
+int main() {
+    // Sparse cases use a compare chain; `default` may come before a case.
+    int r = 0;
+    for (int x = 0; x < 10; x++) {
+        switch (x) {
+        case 0:
+            r += 1;
+            break;
+        case 1:
+        case 2:
+            r += 10;
+            break;
+        default:
+            r += 100;
+            break;
+        case 7:
+            r += 1000;
+        }
+    }
+    if (r != 1621) return 1;
+
+    // Dense enough for a jump table.
+    r = 0;
+    for (int x = 2; x < 10; x++) {
+        switch (x) {
+        case 3: r += 3;
+        case 4: r += 4;
+        case 5: r += 5; break;
+        case 6: r *= 2; break;
+        case 7: r += 7;
+        }
+    }
+    if (r != 59) return 2;
+
+    // A jump table with negative cases.
+    r = 0;
+    for (int x = -4; x < 4; x++) {
+        switch (x) {
+        case -3: r += 1; break;
+        case -2: r += 2; break;
+        case -1: r += 4; break;
+        case 0: r += 8; break;
+        case 1: r += 16; break;
+        case 2: r += 32; break;
+        }
+    }
+    if (r != 63) return 3;
+
+    // Falling through from `default` into a later case.
+    r = 0;
+    int y = 5;
+    switch (y) {
+    default:
+        r += 1;
+    case 1:
+        r += 10;
+        break;
+    case 2:
+        r += 100;
+    }
+    if (r != 11) return 4;
+
+    // No matching case and no default.
+    switch (y) {
+    case 1:
+        r = 0;
+    }
+    if (r != 11) return 5;
+
+    r = 0;
+    for (int a = 1; a <= 2; a++) {
+        for (int b = 1; b <= 3; b += 2) {
+            switch (a) {
+            case 1:
+                switch (b) {
+                case 1: r += 11; break;
+                default: r += 19;
+                }
+                break;
+            case 2:
+                r += 2;
+            }
+        }
+    }
+    if (r != 34) return 6;
+
+    // `continue` goes to the enclosing loop; `break` only leaves the switch.
+    int sum = 0;
+    for (int i = 0; i < 10; i++) {
+        switch (i % 3) {
+        case 0: continue;
+        case 1: sum += i; break;
+        default: sum += 100;
+        }
+        sum++;
+    }
+    if (sum != 318) return 7;
+
+    if (declared(2) != 20 || declared(3) != 0) return 8;
+    if (classify(0) != 10 || classify(4) != 30 || classify(7) != 70) return 9;
+    return 0;
+}
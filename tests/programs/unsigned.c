@@ -0,0 +1,59 @@
+// Unsigned arithmetic wraps and divides, shifts and compares without sign,
+// and narrow types are sign- or zero-extended when they are promoted.
+int main() {
+    unsigned a = 4294967295u;
+    if (a + 1 != 0) return 1;
+    if (a / 2 != 2147483647) return 2;
+    if (a % 10 != 5) return 3;
+    if (a >> 28 != 15) return 4;
+    int s = -1;
+    if (s >> 28 != -1) return 5;
+    // Comparisons convert the signed operand to unsigned.
+    if (!(a > 1)) return 6;
+    if (s < 1u) return 7;
+    if (!(s == a)) return 8;
+    unsigned b = 3;
+    if (b - 5 < b) return 9;
+    if (-7 / 2 != -3 || -7 % 2 != -1) return 10;
+    if (-7u / 2 != 2147483644) return 11;
+    unsigned long big = 18446744073709551615ul;
+    if (big / 3 != 6148914691236517205ul) return 12;
+    if (big >> 60 != 15) return 13;
+    if (big % 1000 != 615) return 14;
+    long neg = -1;
+    if (neg >> 60 != -1) return 15;
+    if (neg < 0ul) return 16;
+
+    // Narrow types keep their sign when widened.
+    char c = -1;
+    unsigned char uc = 255;
+    if (c != -1 || uc != 255) return 17;
+    if (c == uc) return 18;
+    signed char sc = 200;
+    if (sc != -56) return 19;
+    short sh = -2;
+    unsigned short us = 65534;
+    if (sh != -2 || us != 65534) return 20;
+    long wide = sh;
+    if (wide != -2) return 21;
+    wide = us;
+    if (wide != 65534) return 22;
+    unsigned long uwide = c;
+    if (uwide != 18446744073709551615ul) return 23;
+    uwide = uc;
+    if (uwide != 255) return 24;
+    // Conversions to narrower types keep the low bits.
+    uc = 0x1234;
+    if (uc != 0x34) return 25;
+    sh = 70000;
+    if (sh != 4464) return 26;
+    int i = 4294967295u;
+    if (i != -1) return 27;
+    // Both operands are promoted to int before unsigned char arithmetic.
+    unsigned char x = 200;
+    unsigned char y = 100;
+    if (x + y != 300) return 28;
+    x += y;
+    if (x != 44) return 29;
+    return 0;
+}
@@ -0,0 +1,27 @@
+// `void` functions, and casts to `void` that discard a value.
+int g;
+void set(int c) { g = c; }
+void nothing(void) {}
+
+// `return;` leaves a void function early.
+void set_positive(int c) {
+    if (c < 0)
+        return;
+    g = c;
+    return;
+}
+
+int main(void) {
+    int x = 3;
+    (void)x;
+    set(5);
+    nothing();
+    if (g != 5) return 1;
+    (void)set(2);
+    if (g != 2) return 2;
+    set_positive(-4);
+    if (g != 2) return 3;
+    set_positive(4);
+    if (g != 4) return 4;
+    return 0;
+}